@@ -0,0 +1,249 @@
+use crate::{
+    AppError,
+    db::{Database, ImportMode, TaggedFile},
+};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// Rules file is read from database root
+pub const RULES_FILENAME: &str = ".anytaggy-rules.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<RuleDefinition>,
+}
+
+/// All of the specified conditions have to match for tags to be added
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDefinition {
+    name: String,
+    tags: Vec<String>,
+    /// Extensions without dot, compared case-insensitively
+    extensions: Option<Vec<String>>,
+    /// Glob matched against path relative to database root
+    glob: Option<String>,
+    /// Regex matched against file name
+    name_regex: Option<String>,
+    /// Size in bytes, inclusive
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    /// Sniffed from file contents, e.g. `image/jpeg` or `image/*`
+    mime_type: Option<String>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    tags: Vec<String>,
+    extensions: Option<Vec<String>>,
+    glob: Option<GlobMatcher>,
+    name_regex: Option<Regex>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    mime_type: Option<String>,
+}
+
+/// Tag added to a file by a rule
+#[derive(Debug)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    /// Loads rules from database root, missing rules file is the same as an empty one
+    pub fn load(root: &Path) -> Result<Self, AppError> {
+        match std::fs::read_to_string(root.join(RULES_FILENAME)) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, AppError> {
+        let rules_file: RulesFile =
+            toml::from_str(contents).map_err(|err| AppError::InvalidRules(err.message().into()))?;
+        let rules = rules_file
+            .rules
+            .into_iter()
+            .map(|definition| {
+                let invalid =
+                    |err: String| AppError::InvalidRules(format!("{}: {err}", definition.name));
+                Ok(Rule {
+                    glob: definition
+                        .glob
+                        .as_deref()
+                        .map(|glob| Glob::new(glob).map(|glob| glob.compile_matcher()))
+                        .transpose()
+                        .map_err(|err| invalid(err.to_string()))?,
+                    name_regex: definition
+                        .name_regex
+                        .as_deref()
+                        .map(Regex::new)
+                        .transpose()
+                        .map_err(|err| invalid(err.to_string()))?,
+                    extensions: definition.extensions.map(|extensions| {
+                        extensions
+                            .iter()
+                            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+                            .collect()
+                    }),
+                    name: definition.name,
+                    tags: definition.tags,
+                    min_size: definition.min_size,
+                    max_size: definition.max_size,
+                    mime_type: definition.mime_type,
+                })
+            })
+            .collect::<Result<_, AppError>>()?;
+
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Tags of all rules matching the file, `path` is relative to database root
+    pub fn matches(&self, path: &Path, file_path: &Path) -> Result<Vec<RuleMatch>, AppError> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let size = std::fs::metadata(file_path)?.len();
+        // Contents are only read when some rule needs them
+        let mut mime_type = None;
+
+        let mut matches = Vec::new();
+        for rule in &self.rules {
+            let is_match = rule.extensions.as_ref().is_none_or(|extensions| {
+                extension
+                    .as_ref()
+                    .is_some_and(|extension| extensions.contains(extension))
+            }) && rule.glob.as_ref().is_none_or(|glob| glob.is_match(path))
+                && rule
+                    .name_regex
+                    .as_ref()
+                    .is_none_or(|regex| regex.is_match(&name))
+                && rule.min_size.is_none_or(|min_size| size >= min_size)
+                && rule.max_size.is_none_or(|max_size| size <= max_size);
+            if !is_match {
+                continue;
+            }
+            if let Some(expected) = &rule.mime_type {
+                if mime_type.is_none() {
+                    mime_type = Some(super::detect_mime_type(file_path)?);
+                }
+                if !mime_type
+                    .flatten()
+                    .is_some_and(|mime_type| mime_type_matches(expected, mime_type))
+                {
+                    continue;
+                }
+            }
+
+            debug!("{} matched {}", path.display(), rule.name);
+            matches.extend(rule.tags.iter().map(|tag| RuleMatch {
+                rule: rule.name.clone(),
+                tag: tag.clone(),
+            }));
+        }
+
+        Ok(matches)
+    }
+}
+
+/// `image/*` matches any image, otherwise types have to be equal
+fn mime_type_matches(expected: &str, mime_type: &str) -> bool {
+    match expected.strip_suffix("/*") {
+        Some(top_level) => mime_type
+            .split_once('/')
+            .is_some_and(|(mime_top_level, _)| mime_top_level == top_level),
+        None => expected == mime_type,
+    }
+}
+
+/// Applies rules to files, directories are searched recursively.
+/// Own files of anytaggy, like database and rules file, are skipped
+pub fn autotag(
+    db: &mut Database,
+    root: &Path,
+    paths: &[PathBuf],
+    dry_run: bool,
+) -> Result<Option<String>, AppError> {
+    let rules = Rules::load(root)?;
+    if rules.is_empty() {
+        return Err(AppError::NoRules);
+    }
+
+    let mut file_paths = Vec::new();
+    if paths.is_empty() {
+        super::collect_files(root, &mut file_paths)?;
+    }
+    for path in paths {
+        if path.is_dir() {
+            super::collect_files(path, &mut file_paths)?;
+        } else if path.is_file() {
+            file_paths.push(path.clone());
+        } else {
+            return Err(AppError::FileNotFound);
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut files = Vec::new();
+    for file_path in file_paths {
+        if super::is_anytaggy_file(db, &file_path) {
+            continue;
+        }
+        // Contents are hashed only for files matching some rule
+        let path = super::relative_file_path(root, &file_path)?;
+        let matches = rules.matches(&path, &file_path)?;
+        if matches.is_empty() {
+            continue;
+        }
+        let file = super::prepare_file_arg(root, &file_path)?;
+        lines.extend(
+            matches.iter().map(|rule_match| {
+                format!("{}: {} ({})", file.path, rule_match.tag, rule_match.rule)
+            }),
+        );
+        files.push(TaggedFile {
+            file,
+            tags: matches
+                .into_iter()
+                .map(|rule_match| rule_match.tag)
+                .collect(),
+        });
+    }
+
+    let tags: Vec<String> = files
+        .iter()
+        .flat_map(|tagged_file| tagged_file.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let mode = if dry_run {
+        ImportMode::DryRun
+    } else {
+        ImportMode::Merge
+    };
+    let report = db.import_files(&tags, &files, mode)?;
+    lines.push(super::import::format_report(&report, mode));
+
+    Ok(Some(lines.join("\n")))
+}
@@ -0,0 +1,207 @@
+use crate::{AppError, Args, CompletionShell, db::Database};
+use clap::CommandFactory;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Hidden command called by completion scripts, see [`complete`]
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// Option with tags as its value, when it's written as a single word
+const TAGS_OPTION_PREFIX: &str = "--tags=";
+
+/// Tried before completions generated by clap, which are used when it fails
+const BASH_DYNAMIC: &str = r#"
+_{name}_dynamic() {
+    local candidates
+    if candidates=$({name} __complete "$COMP_CWORD" "${COMP_WORDS[@]}" 2>/dev/null); then
+        local IFS=$'\n'
+        COMPREPLY=($candidates)
+        return 0
+    fi
+    _{name} "$@"
+}
+complete -F _{name}_dynamic -o bashdefault -o default {name}"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_{name}_dynamic() {
+    local candidates
+    if candidates=$({name} __complete $((CURRENT - 1)) "${words[@]}" 2>/dev/null); then
+        compadd -- ${(f)candidates}
+        return
+    fi
+    _{name} "$@"
+}
+compdef _{name}_dynamic {name}"#;
+
+const FISH_DYNAMIC: &str = r#"
+function __{name}_dynamic
+    set -l previous (commandline -opc)
+    {name} __complete (count $previous) $previous (commandline -ct) 2>/dev/null
+end
+complete -c {name} -f -n '__{name}_dynamic >/dev/null' -a '(__{name}_dynamic)'"#;
+
+/// Completion script generated from arguments definition,
+/// extended to complete tag names through [`COMPLETE_COMMAND`]
+pub fn completions(shell: CompletionShell) -> Result<Option<String>, AppError> {
+    let mut command = Args::command();
+    let name = command.get_name().to_string();
+
+    let (generator, dynamic) = match shell {
+        CompletionShell::Bash => (clap_complete::Shell::Bash, BASH_DYNAMIC),
+        CompletionShell::Zsh => (clap_complete::Shell::Zsh, ZSH_DYNAMIC),
+        CompletionShell::Fish => (clap_complete::Shell::Fish, FISH_DYNAMIC),
+    };
+    let mut script = Vec::new();
+    clap_complete::generate(generator, &mut command, &name, &mut script);
+
+    Ok(Some(format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&script).trim_end(),
+        dynamic.replace("{name}", &name)
+    )))
+}
+
+/// Database specified in words being completed, it's used instead of the nearest one
+#[must_use]
+pub fn database_path_arg(words: &[String]) -> Option<PathBuf> {
+    let mut words = words.iter().skip(1);
+    while let Some(word) = words.next() {
+        if word == "-d" || word == "--database-path" {
+            return words.next().map(PathBuf::from);
+        }
+        if let Some(database_path) = word.strip_prefix("--database-path=") {
+            return Some(database_path.into());
+        }
+        if !word.starts_with('-') {
+            break;
+        }
+    }
+    None
+}
+
+/// Tag names to complete word at `index` of command line `words`, one per line.
+/// Fails when the word is not a tag, so completion script falls back to other completions
+pub fn complete(
+    db: &Database,
+    roots: &[PathBuf],
+    index: usize,
+    words: &[String],
+) -> Result<Option<String>, AppError> {
+    let (words, index, kept) = join_split_words(words, index);
+    let current = words.get(index).map_or("", String::as_str);
+    let (subcommand, arguments) =
+        split_subcommand(words.get(1..index).unwrap_or_default()).ok_or(AppError::NoCompletions)?;
+    let previous = arguments.last().map(String::as_str);
+    debug!("subcommand: {subcommand}, previous: {previous:?}, current: {current}, kept: {kept}");
+
+    let (prefix, value, chosen): (&str, &str, Vec<&str>) = match (subcommand, previous) {
+        (_, Some("--database" | "--discover")) => return Err(AppError::NoCompletions),
+        ("files" | "exec", _)
+            if !current.starts_with('-') && !arguments.iter().any(|word| word == "--") =>
+        {
+            ("", current, positional(arguments).collect())
+        }
+        ("tag" | "untag", Some("-t" | "--tags")) => ("", current, Vec::new()),
+        ("tag" | "untag", _) if current.starts_with(TAGS_OPTION_PREFIX) => (
+            TAGS_OPTION_PREFIX,
+            &current[TAGS_OPTION_PREFIX.len()..],
+            Vec::new(),
+        ),
+        ("rm-tags", _) if !current.starts_with('-') => ("", current, Vec::new()),
+        _ => return Err(AppError::NoCompletions),
+    };
+    // Comma separated values are completed after the last comma
+    let (listed, partial) = match (subcommand, value.rsplit_once(',')) {
+        ("tag" | "untag" | "rm-tags", Some((listed, partial))) => (format!("{listed},"), partial),
+        _ => (String::new(), value),
+    };
+    let chosen: Vec<&str> = chosen.into_iter().chain(listed.split(',')).collect();
+
+    let tags = if subcommand == "untag" {
+        match positional(arguments).next() {
+            Some(file_path) => super::tags::file_tags(db, roots, Path::new(file_path))?,
+            None => Vec::new(),
+        }
+    } else {
+        db.get_all_tags()?
+    };
+    let candidates: Vec<String> = tags
+        .into_iter()
+        .filter(|tag| tag.starts_with(partial) && !chosen.contains(&tag.as_str()))
+        .map(|tag| {
+            let candidate = format!("{prefix}{listed}{tag}");
+            match candidate.strip_prefix(&kept) {
+                Some(replaced) => replaced.to_string(),
+                None => candidate,
+            }
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(candidates.join("\n")))
+    }
+}
+
+/// Bash splits words at ':' and '=', like 'exif:camera=X' or '--tags=a,b', they are joined back.
+/// Returns joined words, index of the current one and its beginning which bash doesn't replace,
+/// so candidates are printed without it
+fn join_split_words(words: &[String], index: usize) -> (Vec<String>, usize, String) {
+    let is_separator = |word: &str| word == ":" || word == "=";
+    let mut joined: Vec<String> = Vec::new();
+    let mut joined_index = None;
+    let mut kept = String::new();
+    for (word_index, word) in words.iter().enumerate() {
+        // Empty word after separator is a new one, started after whitespace
+        let continues = word_index > 1
+            && !word.is_empty()
+            && (is_separator(word) || is_separator(&words[word_index - 1]));
+        match joined.last_mut() {
+            Some(last) if continues => last.push_str(word),
+            _ => joined.push(word.clone()),
+        }
+
+        if word_index == index {
+            joined_index = Some(joined.len() - 1);
+            let last = &joined[joined.len() - 1];
+            // Bash replaces text after the last separator, nothing when cursor is right after it
+            kept = if is_separator(word) {
+                last.clone()
+            } else {
+                last[..last.len() - word.len()].to_string()
+            };
+        }
+    }
+
+    let index = joined_index.unwrap_or(joined.len());
+    (joined, index, kept)
+}
+
+/// Subcommand and words following it, global options before it are skipped
+fn split_subcommand(words: &[String]) -> Option<(&str, &[String])> {
+    let mut index = 0;
+    while let Some(word) = words.get(index) {
+        if word == "-d" || word == "--database-path" {
+            index += 2;
+        } else if word.starts_with('-') {
+            index += 1;
+        } else {
+            return Some((word, &words[index + 1..]));
+        }
+    }
+    None
+}
+
+/// Arguments which are not options or their values
+fn positional(arguments: &[String]) -> impl Iterator<Item = &str> {
+    let mut previous: Option<&str> = None;
+    arguments.iter().filter_map(move |word| {
+        let option_value = matches!(
+            previous,
+            Some("-t" | "--tags" | "--database" | "--discover")
+        );
+        previous = Some(word);
+        (!option_value && !word.starts_with('-')).then_some(word.as_str())
+    })
+}
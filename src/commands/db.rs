@@ -0,0 +1,106 @@
+use crate::{
+    AppError,
+    db::{Database, File, Setting},
+};
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+};
+use tracing::debug;
+
+/// Applies pending migrations, then rewrites paths stored by older versions relative to `root`
+/// and detects types of files stored before types were known.
+/// Path is only rewritten when the file found there has the stored contents,
+/// other files are left as is and reported on stderr
+pub fn upgrade(db: &mut Database, root: &Path) -> Result<Option<String>, AppError> {
+    let version = db.upgrade()?;
+
+    let stored_files = db.get_stored_files()?;
+    let stored_paths: HashSet<&str> = stored_files.iter().map(|file| file.path.as_str()).collect();
+    let mut updated = Vec::new();
+    for file in &stored_files {
+        let path = match root_relative_path(root, &file.path) {
+            None => {
+                eprintln!(
+                    "WARNING: {} is outside of database root, left as is",
+                    file.path
+                );
+                file.path.clone()
+            }
+            Some(path) if path == file.path => path,
+            Some(path) => {
+                let file_path = root.join(&path);
+                if stored_paths.contains(path.as_str()) {
+                    eprintln!(
+                        "WARNING: {path} is stored already, {} left as is",
+                        file.path
+                    );
+                    continue;
+                }
+                if !file_path.is_file()
+                    || super::get_file_contents_hash(&file_path)? != file.contents_hash
+                {
+                    eprintln!(
+                        "WARNING: {path} is missing or has other contents, {} left as is",
+                        file.path
+                    );
+                    continue;
+                }
+                path
+            }
+        };
+        let file_path = root.join(&path);
+        let mime_type = match &file.mime_type {
+            None if file_path.is_file() => super::detect_mime_type(&file_path)?.map(String::from),
+            mime_type => mime_type.clone(),
+        };
+        if path == file.path && mime_type == file.mime_type {
+            continue;
+        }
+
+        debug!("updating {} to {path}, mime_type: {mime_type:?}", file.path);
+        updated.push(File {
+            fingerprint_hash: super::get_fingerprint_hash(&file.contents_hash, &path)?,
+            path,
+            mime_type,
+            ..file.clone()
+        });
+    }
+    if !updated.is_empty() {
+        db.update_stored_files(&updated)?;
+    }
+
+    Ok(Some(format!("Database schema is at version {version}")))
+}
+
+/// Older versions stored paths as given, either absolute or relative to current directory,
+/// which is assumed to be the root. Paths are normalized without accessing files,
+/// as they may be gone already
+fn root_relative_path(root: &Path, path: &str) -> Option<String> {
+    let mut normalized = PathBuf::new();
+    for component in root.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    Some(normalized.strip_prefix(root).ok()?.display().to_string())
+}
+
+pub fn set_setting(
+    db: &mut Database,
+    setting: Setting,
+    enabled: bool,
+) -> Result<Option<String>, AppError> {
+    db.set_setting(setting, enabled)?;
+
+    Ok(None)
+}
+
+pub fn get_setting(db: &Database, setting: Setting) -> Result<Option<String>, AppError> {
+    Ok(Some(db.get_setting(setting)?.to_string()))
+}
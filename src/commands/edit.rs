@@ -0,0 +1,233 @@
+use crate::{
+    AppError,
+    db::{Database, File, FileEdit, Setting, format_tag_line, parse_tag_line},
+};
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process,
+};
+use tracing::debug;
+
+/// Editor used when neither `VISUAL` nor `EDITOR` is set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Names of temporary file tried before giving up
+const MAX_EDIT_FILE_ATTEMPTS: u32 = 100;
+
+/// File listed for editing
+struct EditedFile {
+    file_path: PathBuf,
+    /// Stored file, `None` when file has no tags yet
+    file: Option<File>,
+    tags: Vec<String>,
+}
+
+/// Lists files with their tags in editor, one `path<TAB>tag1,tag2` line per file,
+/// and applies changed tags once editor is closed. Removed lines leave files unchanged.
+/// Targets are paths when all of them exist, otherwise they are tags to query files by
+pub fn edit(
+    db: &mut Database,
+    root: &Path,
+    targets: &[String],
+    editor: Option<&str>,
+) -> Result<Option<String>, AppError> {
+    let mut listed = list_files(db, root, targets)?;
+    if listed.is_empty() {
+        return Ok(Some("No files to edit".into()));
+    }
+    let contents: String = listed
+        .iter()
+        .map(|(path, edited_file)| format!("{}\n", format_tag_line(path, &edited_file.tags)))
+        .collect();
+
+    let edit_path = create_edit_file(&contents)?;
+    let edited =
+        run_editor(editor, &edit_path).and_then(|()| Ok(std::fs::read_to_string(&edit_path)?));
+    std::fs::remove_file(&edit_path)?;
+    let edited = edited?;
+
+    // Everything is validated before database is changed
+    let mut edits = Vec::new();
+    let mut lines = Vec::new();
+    let mut unchanged = 0;
+    let mut seen = Vec::new();
+    for (index, line) in edited.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |reason: &str| AppError::InvalidEdit(index + 1, reason.into());
+        let (path, tags) = parse_tag_line(line)
+            .ok_or_else(|| invalid("expected path, tab and comma separated tags"))?;
+        if seen.contains(&path) {
+            return Err(invalid(&format!("file is listed twice: {path}")));
+        }
+        let Some(edited_file) = listed.get_mut(&path) else {
+            return Err(invalid(&format!("file was not listed: {path}")));
+        };
+        seen.push(path.clone());
+
+        let added: Vec<String> = tags
+            .iter()
+            .filter(|tag| !edited_file.tags.contains(tag))
+            .cloned()
+            .collect();
+        let removed: Vec<String> = edited_file
+            .tags
+            .iter()
+            .filter(|tag| !tags.contains(tag))
+            .cloned()
+            .collect();
+        if added.is_empty() && removed.is_empty() {
+            unchanged += 1;
+            continue;
+        }
+
+        let file = match edited_file.file.take() {
+            Some(file) => file,
+            None => super::prepare_file_arg(root, &edited_file.file_path)?,
+        };
+        lines.push(format!(
+            "Updated: {path} (+{},-{})",
+            added.join(","),
+            removed.join(",")
+        ));
+        edits.push(FileEdit {
+            file,
+            added,
+            removed,
+        });
+    }
+    debug!("edits: {edits:?}");
+
+    db.edit_files(&edits)?;
+    if db.get_setting(Setting::XattrSync)? {
+        for edit in &edits {
+            super::xattr::sync_file(db, &root.join(&edit.file.path), &edit.file.fingerprint_hash);
+        }
+    }
+    lines.push(format!("Unchanged: {unchanged} files"));
+
+    Ok(Some(lines.join("\n")))
+}
+
+/// Files of the main database by stored path
+fn list_files(
+    db: &Database,
+    root: &Path,
+    targets: &[String],
+) -> Result<BTreeMap<String, EditedFile>, AppError> {
+    let mut tagged_files: BTreeMap<String, EditedFile> = db
+        .get_tagged_files()?
+        .into_iter()
+        .map(|tagged_file| {
+            (
+                tagged_file.file.path.clone(),
+                EditedFile {
+                    file_path: root.join(&tagged_file.file.path),
+                    file: Some(tagged_file.file),
+                    tags: tagged_file.tags,
+                },
+            )
+        })
+        .collect();
+
+    if targets.is_empty() {
+        return Ok(tagged_files);
+    }
+
+    if !targets.iter().all(|target| Path::new(target).exists()) {
+        let query: Vec<&str> = targets.iter().map(String::as_str).collect();
+        let mut listed = BTreeMap::new();
+        for stored_path in db.get_files_by_tags(&query)? {
+            if stored_path.database != 0 {
+                continue;
+            }
+            if let Some(edited_file) = tagged_files.remove(&stored_path.path) {
+                listed.insert(stored_path.path, edited_file);
+            }
+        }
+        return Ok(listed);
+    }
+
+    let mut file_paths = Vec::new();
+    for target in targets {
+        let target = Path::new(target);
+        if target.is_dir() {
+            super::collect_files(target, &mut file_paths)?;
+        } else {
+            file_paths.push(target.to_path_buf());
+        }
+    }
+    let mut listed = BTreeMap::new();
+    for file_path in file_paths {
+        if super::is_anytaggy_file(db, &file_path) {
+            continue;
+        }
+        let path = super::relative_file_path(root, &file_path)?
+            .display()
+            .to_string();
+        let edited_file = tagged_files.remove(&path).unwrap_or(EditedFile {
+            file_path,
+            file: None,
+            tags: Vec::new(),
+        });
+        listed.insert(path, edited_file);
+    }
+
+    Ok(listed)
+}
+
+/// Creates temporary file readable only by current user, existing files and symlinks
+/// are never reused, so another name is tried when one is taken
+fn create_edit_file(contents: &str) -> Result<PathBuf, AppError> {
+    let mut attempt = 0;
+    loop {
+        let edit_path =
+            std::env::temp_dir().join(format!("anytaggy-edit-{}-{attempt}.txt", process::id()));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&edit_path)
+        {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                debug!("edit file: {}", edit_path.display());
+                return Ok(edit_path);
+            }
+            Err(err)
+                if err.kind() == ErrorKind::AlreadyExists && attempt < MAX_EDIT_FILE_ATTEMPTS =>
+            {
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Runs editor through shell, so it could have arguments like `code --wait`
+fn run_editor(editor: Option<&str>, edit_path: &Path) -> Result<(), AppError> {
+    let editor = editor
+        .map(String::from)
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.into());
+    debug!("editor: {editor}");
+
+    let status = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(edit_path)
+        .status()?;
+    if !status.success() {
+        return Err(AppError::EditorFailed(editor));
+    }
+
+    Ok(())
+}
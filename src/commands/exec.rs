@@ -0,0 +1,118 @@
+use crate::{AppError, db::Database};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process,
+};
+use tracing::{debug, warn};
+
+/// Placeholder replaced with file path in command arguments
+const PLACEHOLDER: &str = "{}";
+/// Size of arguments of a single batch invocation, the default of xargs
+const MAX_BATCH_SIZE: usize = 128 * 1024;
+
+/// Runs command for each file matching tags, or for as many of them at once as fit
+/// in `MAX_BATCH_SIZE` in batch mode.
+/// Paths are passed as separate arguments, so they are never split on spaces.
+/// Every `{}` in arguments is replaced with the path, which is appended when there are none
+pub fn exec(
+    db: &Database,
+    roots: &[PathBuf],
+    tag_names: &[&str],
+    command: &[String],
+    batch: bool,
+) -> Result<Option<String>, AppError> {
+    let (program, args) = command.split_first().ok_or(AppError::NoCommandSpecified)?;
+    let paths: Vec<PathBuf> = db
+        .get_files_by_tags(tag_names)?
+        .into_iter()
+        .map(|file| roots[file.database].join(file.path))
+        .collect();
+    if paths.is_empty() {
+        return Ok(None);
+    }
+
+    let invocations: Vec<Vec<OsString>> = if batch {
+        batch_chunks(program, args, &paths)
+            .into_iter()
+            .map(|chunk| batch_args(args, chunk))
+            .collect()
+    } else {
+        paths.iter().map(|path| file_args(args, path)).collect()
+    };
+    let mut failed = 0;
+    for args in &invocations {
+        debug!("running {program} {args:?}");
+        let status = process::Command::new(program).args(args).status()?;
+        if !status.success() {
+            warn!("{program} {args:?} failed: {status}");
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(AppError::ExecFailed(failed, invocations.len()));
+    }
+
+    Ok(None)
+}
+
+fn file_args(args: &[String], path: &Path) -> Vec<OsString> {
+    if !args.iter().any(|arg| arg.contains(PLACEHOLDER)) {
+        let mut args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        args.push(path.into());
+        return args;
+    }
+
+    let path = path.display().to_string();
+    args.iter()
+        .map(|arg| arg.replace(PLACEHOLDER, &path).into())
+        .collect()
+}
+
+/// Splits paths so that command with each of the chunks stays within `MAX_BATCH_SIZE`,
+/// a path too long for it on its own still gets a chunk
+fn batch_chunks<'a>(program: &str, args: &[String], paths: &'a [PathBuf]) -> Vec<&'a [PathBuf]> {
+    // Arguments are passed to the program terminated with NUL
+    let fixed_size = program.len()
+        + 1
+        + args
+            .iter()
+            .filter(|arg| *arg != PLACEHOLDER)
+            .map(|arg| arg.len() + 1)
+            .sum::<usize>();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut size = fixed_size;
+    for (index, path) in paths.iter().enumerate() {
+        let path_size = path.as_os_str().len() + 1;
+        if index > start && size + path_size > MAX_BATCH_SIZE {
+            chunks.push(&paths[start..index]);
+            start = index;
+            size = fixed_size;
+        }
+        size += path_size;
+    }
+    chunks.push(&paths[start..]);
+
+    chunks
+}
+
+/// Standalone `{}` arguments expand to all paths of the chunk
+fn batch_args(args: &[String], paths: &[PathBuf]) -> Vec<OsString> {
+    let mut batch_args = Vec::new();
+    let mut replaced = false;
+    for arg in args {
+        if arg == PLACEHOLDER {
+            batch_args.extend(paths.iter().map(OsString::from));
+            replaced = true;
+        } else {
+            batch_args.push(arg.into());
+        }
+    }
+    if !replaced {
+        batch_args.extend(paths.iter().map(OsString::from));
+    }
+
+    batch_args
+}
@@ -0,0 +1,51 @@
+use crate::{AppError, db::Database};
+use serde::{Deserialize, Serialize};
+
+/// Version of the exported document format
+pub(super) const FORMAT_VERSION: u32 = 1;
+
+/// Whole database, independent of `SQLite` schema
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Document {
+    pub version: u32,
+    /// All tags, including ones not referenced by any file
+    pub tags: Vec<String>,
+    pub files: Vec<DocumentFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct DocumentFile {
+    /// Path relative to database root
+    pub path: String,
+    pub name: String,
+    pub contents_hash: String,
+    /// Recomputed on import from path and contents hash
+    pub fingerprint_hash: String,
+    /// Missing in documents exported before types were detected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub tags: Vec<String>,
+}
+
+pub fn export_json(db: &Database) -> Result<Option<String>, AppError> {
+    let document = Document {
+        version: FORMAT_VERSION,
+        tags: db.get_all_tags()?,
+        files: db
+            .get_tagged_files()?
+            .into_iter()
+            .map(|tagged_file| DocumentFile {
+                path: tagged_file.file.path,
+                name: tagged_file.file.name,
+                contents_hash: tagged_file.file.contents_hash,
+                fingerprint_hash: tagged_file.file.fingerprint_hash,
+                mime_type: tagged_file.file.mime_type,
+                tags: tagged_file.tags,
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&document)
+        .map(Some)
+        .map_err(|err| AppError::InvalidDocument(err.to_string()))
+}
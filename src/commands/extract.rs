@@ -0,0 +1,171 @@
+use crate::{AppError, db::Database};
+use id3::TagLike;
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// Extracts EXIF of images and tags of audio files into `exif:` and `audio:` value tags.
+/// Only files already in database are updated, metadata is extracted again
+/// only when contents of the file change
+pub fn extract(
+    db: &mut Database,
+    root: &Path,
+    paths: &[PathBuf],
+) -> Result<Option<String>, AppError> {
+    let mut file_paths = Vec::new();
+    if paths.is_empty() {
+        super::collect_files(root, &mut file_paths)?;
+    }
+    for path in paths {
+        if path.is_dir() {
+            super::collect_files(path, &mut file_paths)?;
+        } else if path.is_file() {
+            file_paths.push(path.clone());
+        } else {
+            return Err(AppError::FileNotFound);
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut unchanged = 0;
+    for file_path in file_paths {
+        if super::is_anytaggy_file(db, &file_path) {
+            continue;
+        }
+        // Type is sniffed before hashing, so other files are skipped cheaply
+        let extract_tags: fn(&Path) -> Vec<String> = match super::detect_mime_type(&file_path)? {
+            Some("image/jpeg" | "image/tiff" | "image/heif" | "image/png" | "image/webp") => {
+                exif_tags
+            }
+            Some("audio/mpeg") => id3_tags,
+            Some("audio/x-flac") => vorbis_tags,
+            _ => continue,
+        };
+        let file = super::prepare_file_arg(root, &file_path)?;
+        if db.is_metadata_current(&file)? {
+            unchanged += 1;
+            continue;
+        }
+
+        let tags = extract_tags(&file_path);
+        debug!("{}: {tags:?}", file.path);
+
+        let Some(change) = db.set_metadata_tags(&file, &tags)? else {
+            debug!("{} is not in database, skipped", file.path);
+            continue;
+        };
+        if change.added.is_empty() && change.removed.is_empty() {
+            unchanged += 1;
+            continue;
+        }
+        let changes: Vec<String> = change
+            .added
+            .iter()
+            .map(|tag| format!("+{tag}"))
+            .chain(change.removed.iter().map(|tag| format!("-{tag}")))
+            .collect();
+        lines.push(format!("Extracted: {} ({})", file.path, changes.join(",")));
+    }
+    lines.push(format!("Unchanged: {unchanged} files"));
+
+    Ok(Some(lines.join("\n")))
+}
+
+/// Files with broken or missing metadata simply get no tags
+fn exif_tags(file_path: &Path) -> Vec<String> {
+    let Ok(exif) = File::open(file_path)
+        .map(BufReader::new)
+        .and_then(|mut reader| {
+            exif::Reader::new()
+                .read_from_container(&mut reader)
+                .map_err(std::io::Error::other)
+        })
+    else {
+        return Vec::new();
+    };
+    let ascii = |tag| {
+        exif.get_field(tag, exif::In::PRIMARY)
+            .and_then(|field| match &field.value {
+                exif::Value::Ascii(values) => values.first(),
+                _ => None,
+            })
+            .map(|value| String::from_utf8_lossy(value).trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut tags = Vec::new();
+    // Model usually already contains make, e.g. "Canon EOS 5D"
+    let camera = match (ascii(exif::Tag::Make), ascii(exif::Tag::Model)) {
+        (Some(make), Some(model)) if !model.starts_with(&make) => Some(format!("{make} {model}")),
+        (_, Some(model)) => Some(model),
+        (make, None) => make,
+    };
+    if let Some(camera) = camera {
+        tags.push(format!("exif:camera={camera}"));
+    }
+    if let Some(lens) = ascii(exif::Tag::LensModel) {
+        tags.push(format!("exif:lens={lens}"));
+    }
+    // Stored as "2017:05:01 12:00:00"
+    if let Some(date) = ascii(exif::Tag::DateTimeOriginal)
+        .and_then(|date_time| date_time.get(..10).map(|date| date.replace(':', "-")))
+    {
+        tags.push(format!("exif:date_taken={date}"));
+    }
+    if exif
+        .get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY)
+        .is_some()
+    {
+        tags.push("exif:gps".into());
+    }
+
+    tags
+}
+
+fn id3_tags(file_path: &Path) -> Vec<String> {
+    let Ok(tag) = id3::Tag::read_from_path(file_path) else {
+        return Vec::new();
+    };
+
+    audio_tags(
+        tag.artist(),
+        tag.album(),
+        tag.year()
+            .or_else(|| tag.date_recorded().map(|date| date.year)),
+    )
+}
+
+fn vorbis_tags(file_path: &Path) -> Vec<String> {
+    let Ok(tag) = metaflac::Tag::read_from_path(file_path) else {
+        return Vec::new();
+    };
+    let Some(comments) = tag.vorbis_comments() else {
+        return Vec::new();
+    };
+    let first = |values: Option<&Vec<String>>| values.and_then(|values| values.first()).cloned();
+
+    audio_tags(
+        first(comments.artist()).as_deref(),
+        first(comments.album()).as_deref(),
+        // Dates are usually "2017" or "2017-05-01"
+        first(comments.get("DATE")).and_then(|date| date.get(..4)?.parse().ok()),
+    )
+}
+
+fn audio_tags(artist: Option<&str>, album: Option<&str>, year: Option<i32>) -> Vec<String> {
+    [
+        ("artist", artist.map(str::trim).map(String::from)),
+        ("album", album.map(str::trim).map(String::from)),
+        ("year", year.map(|year| year.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| {
+        value
+            .filter(|value| !value.is_empty())
+            .map(|value| format!("audio:{name}={value}"))
+    })
+    .collect()
+}
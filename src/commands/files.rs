@@ -1,20 +1,37 @@
-use crate::{AppError, db::Database};
+use crate::{
+    AppError,
+    db::{Database, StoredPath},
+};
+use std::path::PathBuf;
 
-pub fn get_file_paths(db: &Database, tag_names: &[&str]) -> Result<Option<String>, AppError> {
+pub fn get_file_paths(
+    db: &Database,
+    roots: &[PathBuf],
+    tag_names: &[&str],
+) -> Result<Option<String>, AppError> {
     let files = db.get_files_by_tags(tag_names)?;
     if files.is_empty() {
         Ok(None)
     } else {
-        Ok(Some(files.join("\n")))
+        Ok(Some(resolve_paths(roots, &files)))
     }
 }
 
-pub fn get_files(db: &Database) -> Result<Option<String>, AppError> {
+pub fn get_files(db: &Database, roots: &[PathBuf]) -> Result<Option<String>, AppError> {
     let files = db.get_files()?;
 
     if files.is_empty() {
         Ok(None)
     } else {
-        Ok(Some(files.join("\n")))
+        Ok(Some(resolve_paths(roots, &files)))
     }
 }
+
+/// Resolves paths stored in databases against their roots
+fn resolve_paths(roots: &[PathBuf], paths: &[StoredPath]) -> String {
+    paths
+        .iter()
+        .map(|path| roots[path.database].join(&path.path).display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,84 @@
+use crate::{
+    AppError,
+    db::{Database, File, ImportMode, ImportReport, TaggedFile},
+};
+use std::{io::Read, path::Path};
+use tracing::debug;
+
+use super::export::{Document, FORMAT_VERSION};
+
+/// Imports document exported with `export`, `-` reads it from stdin
+pub fn import_json(
+    db: &mut Database,
+    document_path: &Path,
+    mode: ImportMode,
+) -> Result<Option<String>, AppError> {
+    let contents = if document_path == Path::new("-") {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        contents
+    } else {
+        std::fs::read_to_string(document_path)?
+    };
+    let document: Document = serde_json::from_str(&contents)
+        .map_err(|err| AppError::InvalidDocument(err.to_string()))?;
+    if document.version != FORMAT_VERSION {
+        return Err(AppError::UnsupportedDocumentVersion(document.version));
+    }
+    debug!("importing {} files", document.files.len());
+
+    let files = document
+        .files
+        .into_iter()
+        .map(|file| {
+            Ok(TaggedFile {
+                file: File {
+                    fingerprint_hash: super::get_fingerprint_hash(&file.contents_hash, &file.path)?,
+                    path: file.path,
+                    name: file.name,
+                    contents_hash: file.contents_hash,
+                    mime_type: file.mime_type,
+                },
+                tags: file.tags,
+            })
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    let report = db.import_files(&document.tags, &files, mode)?;
+
+    Ok(Some(format_report(&report, mode)))
+}
+
+/// One line per added or updated file, followed by count of unchanged files.
+/// Updated files list added tags after `+` and removed ones after `-`
+pub(super) fn format_report(report: &ImportReport, mode: ImportMode) -> String {
+    let (added, updated) = if mode == ImportMode::DryRun {
+        ("Would add", "Would update")
+    } else {
+        ("Added", "Updated")
+    };
+    let mut lines: Vec<String> = report
+        .added
+        .iter()
+        .map(|path| format!("{added}: {path}"))
+        .collect();
+    lines.extend(report.updated.iter().map(|(path, added, removed)| {
+        let mut changes = Vec::new();
+        if !added.is_empty() {
+            changes.push(format!("+{}", added.join(",")));
+        }
+        if !removed.is_empty() {
+            changes.push(format!("-{}", removed.join(",")));
+        }
+        format!("{updated}: {path} ({})", changes.join(","))
+    }));
+    lines.extend(
+        report
+            .deleted_tags
+            .iter()
+            .map(|tag| format!("Deleted tag: {tag}")),
+    );
+    lines.push(format!("Unchanged: {} files", report.unchanged));
+
+    lines.join("\n")
+}
@@ -0,0 +1,64 @@
+use crate::{
+    AppError,
+    db::{Database, ImportMode, TaggedFile, read_tmsu_database},
+};
+use std::{collections::BTreeSet, path::Path};
+use tracing::debug;
+
+/// Imports files and tags from TMSU database.
+/// Files are hashed from disk and stored relative to database root,
+/// things anytaggy has no equivalent for are reported and skipped
+pub fn import_tmsu(
+    db: &mut Database,
+    root: &Path,
+    tmsu_database_path: &Path,
+    mode: ImportMode,
+) -> Result<Option<String>, AppError> {
+    let tmsu = read_tmsu_database(tmsu_database_path)?;
+    debug!("importing {} tmsu files", tmsu.files.len());
+
+    let mut lines = Vec::new();
+    let mut files = Vec::with_capacity(tmsu.files.len());
+    for tmsu_file in tmsu.files {
+        let path = tmsu_file.path.display();
+        if tmsu_file.tags.is_empty() {
+            continue;
+        }
+        if tmsu_file.is_dir {
+            lines.push(format!("Unsupported directory: {path}"));
+            continue;
+        }
+        if !tmsu_file.path.is_file() {
+            lines.push(format!("Missing: {path}"));
+            continue;
+        }
+        let file = match super::prepare_file_arg(root, &tmsu_file.path) {
+            Ok(file) => file,
+            Err(AppError::FileOutsideStructure) => {
+                lines.push(format!("Outside of database: {path}"));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        files.push(TaggedFile {
+            file,
+            tags: tmsu_file.tags,
+        });
+    }
+    lines.extend(
+        tmsu.implications
+            .iter()
+            .map(|(tag, implied)| format!("Unsupported implication: {tag} -> {implied}")),
+    );
+
+    let tags: Vec<String> = files
+        .iter()
+        .flat_map(|tagged_file| tagged_file.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let report = db.import_files(&tags, &files, mode)?;
+    lines.push(super::import::format_report(&report, mode));
+
+    Ok(Some(lines.join("\n")))
+}
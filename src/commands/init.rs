@@ -0,0 +1,21 @@
+use crate::{
+    AppError,
+    db::{Database, DatabaseMode},
+};
+use std::path::Path;
+
+pub fn init(database_path: &Path) -> Result<Option<String>, AppError> {
+    if database_path.exists() {
+        return Err(AppError::DatabaseAlreadyExists(database_path.to_path_buf()));
+    }
+    if let Some(parent) = database_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    Database::new(&DatabaseMode::ReadWriteCreate, database_path)?;
+
+    Ok(Some(format!(
+        "Initialized database in {}",
+        database_path.display()
+    )))
+}
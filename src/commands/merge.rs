@@ -0,0 +1,52 @@
+use crate::{
+    AppError,
+    db::{Database, DatabaseMode, MergePreference},
+};
+use std::path::Path;
+use tracing::debug;
+
+/// Merges files and tags from other database, reporting files present only on one side
+pub fn merge(
+    db: &mut Database,
+    other_database_path: &Path,
+    prefer: MergePreference,
+) -> Result<Option<String>, AppError> {
+    let theirs = Database::new(&DatabaseMode::Read, other_database_path)?;
+    let files = theirs.get_tagged_files()?;
+    debug!("merging {} files", files.len());
+
+    let report = db.merge_files(&files, prefer)?;
+
+    let only_ours = match prefer {
+        MergePreference::Ours => "Only in ours",
+        MergePreference::Theirs => "Removed",
+    };
+    let mut lines: Vec<String> = report
+        .only_theirs
+        .iter()
+        .map(|path| format!("Added: {path}"))
+        .collect();
+    lines.extend(
+        report
+            .only_ours
+            .iter()
+            .map(|path| format!("{only_ours}: {path}")),
+    );
+    lines.extend(report.updated.iter().map(|(path, added, removed)| {
+        let changes: Vec<String> = added
+            .iter()
+            .map(|tag| format!("+{tag}"))
+            .chain(removed.iter().map(|tag| format!("-{tag}")))
+            .collect();
+        format!("Updated: {path} ({})", changes.join(","))
+    }));
+    lines.extend(
+        report
+            .deleted_tags
+            .iter()
+            .map(|tag| format!("Deleted tag: {tag}")),
+    );
+    lines.push(format!("Unchanged: {} files", report.unchanged));
+
+    Ok(Some(lines.join("\n")))
+}
@@ -0,0 +1,19 @@
+use crate::{AppError, db::Database};
+
+/// Lists files marked missing by `watch`, one stored path per line.
+/// Pruning removes them from database, keeping their tags
+pub fn missing(db: &mut Database, prune: bool) -> Result<Option<String>, AppError> {
+    if !prune {
+        let paths = db.get_missing_files()?;
+        return Ok((!paths.is_empty()).then(|| paths.join("\n")));
+    }
+
+    let paths = db.delete_missing_files()?;
+    let mut lines: Vec<String> = paths
+        .iter()
+        .map(|path| format!("Removed: {path}"))
+        .collect();
+    lines.push(format!("Removed {} missing files", paths.len()));
+
+    Ok(Some(lines.join("\n")))
+}
@@ -1,18 +1,57 @@
+pub mod autotag;
+pub mod completions;
+pub mod db;
+pub mod edit;
+pub mod exec;
+pub mod export;
+pub mod extract;
 pub mod files;
+pub mod import;
+pub mod import_tmsu;
+pub mod init;
+pub mod merge;
+pub mod missing;
+#[cfg(feature = "fuse")]
+pub mod mount;
+pub mod rebuild_from_text;
 pub mod rm_tags;
+pub mod rpc;
+pub mod serve;
 pub mod tag;
 pub mod tags;
+pub mod tui;
 pub mod untag;
+pub mod view;
+pub mod watch;
+pub mod xattr;
+pub mod xmp;
 
+use serde::Serialize;
 use sha2::Digest;
 use std::{
     fs::File,
-    io::{self, Write},
-    path::Path,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
 };
 use tracing::debug;
 
-use crate::AppError;
+/// Enough bytes to recognize all of the formats known to `infer`
+const MIME_HEADER_SIZE: usize = 8192;
+
+use crate::{
+    AppError,
+    db::{Database, DatabaseError, text_sidecar_path},
+};
+
+/// Stored file with its tags, as returned by `serve` and `rpc`
+#[derive(Debug, Serialize)]
+pub(super) struct FileDetails {
+    /// Path relative to database root
+    path: String,
+    name: String,
+    mime_type: Option<String>,
+    tags: Vec<String>,
+}
 
 pub(super) fn get_file_contents_hash(file_path: &Path) -> Result<String, AppError> {
     let mut hasher = sha2::Sha256::new();
@@ -35,7 +74,23 @@ pub(super) fn get_fingerprint_hash(
     Ok(format!("{result:x}"))
 }
 
-pub(super) fn prepare_file_arg(file_path: &Path) -> Result<crate::db::File, AppError> {
+/// Path of the file relative to database root.
+/// Only parent directory is canonicalized, so symlinks are stored as themselves
+pub(super) fn relative_file_path(root: &Path, file_path: &Path) -> Result<PathBuf, AppError> {
+    let name = file_path.file_name().ok_or(AppError::NoFileNameFromPath)?;
+    let parent = file_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .canonicalize()?;
+
+    Ok(parent
+        .strip_prefix(root)
+        .map_err(|_| AppError::FileOutsideStructure)?
+        .join(name))
+}
+
+pub(super) fn prepare_file_arg(root: &Path, file_path: &Path) -> Result<crate::db::File, AppError> {
     let name = file_path
         .file_name()
         .ok_or(AppError::NoFileNameFromPath)?
@@ -43,7 +98,7 @@ pub(super) fn prepare_file_arg(file_path: &Path) -> Result<crate::db::File, AppE
         .to_string();
     debug!("name: {name}");
 
-    let path = file_path.display().to_string();
+    let path = relative_file_path(root, file_path)?.display().to_string();
     debug!("path: {path}");
 
     let contents_hash = get_file_contents_hash(file_path)?;
@@ -52,10 +107,94 @@ pub(super) fn prepare_file_arg(file_path: &Path) -> Result<crate::db::File, AppE
     let fingerprint_hash = get_fingerprint_hash(&contents_hash, &path)?;
     debug!("fingerprint_hash: {fingerprint_hash}");
 
+    let mime_type = detect_mime_type(file_path)?.map(String::from);
+    debug!("mime_type: {mime_type:?}");
+
     Ok(crate::db::File {
         path,
         name,
         contents_hash,
         fingerprint_hash,
+        mime_type,
+    })
+}
+
+/// Collects files under directory recursively, sorted by name. Symlinks are not followed
+pub(super) fn collect_files(
+    directory: &Path,
+    file_paths: &mut Vec<PathBuf>,
+) -> Result<(), AppError> {
+    let mut entries = std::fs::read_dir(directory)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), file_paths)?;
+        } else if file_type.is_file() {
+            file_paths.push(entry.path());
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks tags coming from other programs as a whole, so a bad one doesn't leave others applied.
+/// Commas separate tags in listings, so they could not be part of names
+pub(super) fn check_tag_names(tag_names: &[&str]) -> Result<(), AppError> {
+    if tag_names.is_empty() {
+        return Err(AppError::NoTagsSpecified);
+    }
+    if let Some(tag_name) = tag_names
+        .iter()
+        .find(|tag_name| tag_name.trim().is_empty() || tag_name.contains(','))
+    {
+        return Err(AppError::InvalidTagName((*tag_name).into()));
+    }
+
+    Ok(())
+}
+
+/// Own files of anytaggy, like database with its journal, text sidecar and rules file
+pub(super) fn is_anytaggy_file(db: &Database, file_path: &Path) -> bool {
+    let Some(name) = file_path.file_name() else {
+        return false;
+    };
+    let is_database_file = db.path().is_some_and(|database_path| {
+        database_path.file_name().is_some_and(|database_name| {
+            name.to_string_lossy()
+                .starts_with(&*database_name.to_string_lossy())
+        }) || text_sidecar_path(database_path).file_name() == Some(name)
+    });
+
+    is_database_file || name.to_string_lossy().starts_with(".anytaggy")
+}
+
+/// MIME type sniffed from magic bytes at the start of file, extension is not used
+pub(super) fn detect_mime_type(file_path: &Path) -> Result<Option<&'static str>, AppError> {
+    let mut header = Vec::with_capacity(MIME_HEADER_SIZE);
+    File::open(file_path)?
+        .take(MIME_HEADER_SIZE as u64)
+        .read_to_end(&mut header)?;
+
+    Ok(infer::get(&header).map(|kind| kind.mime_type()))
+}
+
+/// Details of the stored file, fails when file has no tags
+pub(super) fn file_details(
+    db: &Database,
+    root: &Path,
+    file_path: &Path,
+) -> Result<FileDetails, AppError> {
+    let file = prepare_file_arg(root, file_path)?;
+    let tags = db.get_file_tags_by_hash(0, &file.fingerprint_hash)?;
+    if tags.is_empty() {
+        return Err(DatabaseError::NoSuchFile.into());
+    }
+
+    Ok(FileDetails {
+        path: file.path,
+        name: file.name,
+        mime_type: file.mime_type,
+        tags,
     })
 }
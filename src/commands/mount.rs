@@ -0,0 +1,454 @@
+use crate::{
+    AppError,
+    db::{Database, DatabaseError},
+};
+use fuser::{
+    Config, Errno, FileAttr, FileHandle, FileType, Filesystem, Generation, INodeNo, MountOption,
+    ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, Request,
+};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, warn};
+
+/// Directory with a directory for every tag
+const TAGS_DIRECTORY: &str = "tags";
+/// Database changes outside of the mount should show up quickly
+const TTL: Duration = Duration::from_secs(1);
+
+/// Mounts tags as directories until the filesystem is unmounted
+pub fn mount(
+    db: Database,
+    roots: Vec<PathBuf>,
+    mountpoint: &Path,
+) -> Result<Option<String>, AppError> {
+    fuser::mount(TagsFilesystem::new(db, roots)?, mountpoint, &config())?;
+
+    Ok(None)
+}
+
+fn config() -> Config {
+    let mut config = Config::default();
+    config.mount_options = vec![
+        MountOption::FSName("anytaggy".into()),
+        MountOption::Subtype("anytaggy".into()),
+        MountOption::DefaultPermissions,
+    ];
+    config
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    Root,
+    Tags,
+    /// Directory of files having all of the tags
+    Query(Vec<String>),
+    /// Link to a file inside of query directory
+    Link {
+        query: Vec<String>,
+        name: String,
+        database: usize,
+        target: PathBuf,
+    },
+}
+
+/// Inode numbers the kernel doesn't reference are dropped once there are more of them,
+/// so browsing a large tree doesn't grow the table for the whole mount
+const MAX_INODES: usize = 10_000;
+
+/// Inode numbers are handed out on first use and kept while the kernel references them
+#[derive(Debug)]
+struct Inodes {
+    /// Nodes by their numbers, along with count of lookups not forgotten by the kernel yet
+    nodes: HashMap<u64, (Node, u64)>,
+    numbers: HashMap<Node, u64>,
+    next: u64,
+}
+
+impl Inodes {
+    fn new() -> Self {
+        Self {
+            nodes: HashMap::from([(INodeNo::ROOT.0, (Node::Root, 1))]),
+            numbers: HashMap::from([(Node::Root, INodeNo::ROOT.0)]),
+            next: INodeNo::ROOT.0 + 1,
+        }
+    }
+
+    fn get(&self, ino: INodeNo) -> Option<&Node> {
+        self.nodes.get(&ino.0).map(|(node, _)| node)
+    }
+
+    /// Number of the node without the kernel referencing it, e.g. for directory entries
+    fn number(&mut self, node: Node) -> INodeNo {
+        if let Some(number) = self.numbers.get(&node) {
+            return INodeNo(*number);
+        }
+        if self.nodes.len() >= MAX_INODES {
+            self.expire();
+        }
+        let number = self.next;
+        self.next += 1;
+        self.nodes.insert(number, (node.clone(), 0));
+        self.numbers.insert(node, number);
+        INodeNo(number)
+    }
+
+    /// Number of the node replied to the kernel, kept until the kernel forgets it
+    fn lookup(&mut self, node: Node) -> INodeNo {
+        let ino = self.number(node);
+        if let Some((_, lookups)) = self.nodes.get_mut(&ino.0) {
+            *lookups += 1;
+        }
+        ino
+    }
+
+    fn forget(&mut self, ino: INodeNo, nlookup: u64) {
+        if ino == INodeNo::ROOT {
+            return;
+        }
+        let Some((node, lookups)) = self.nodes.get_mut(&ino.0) else {
+            return;
+        };
+        *lookups = lookups.saturating_sub(nlookup);
+        if *lookups == 0 {
+            let node = node.clone();
+            self.nodes.remove(&ino.0);
+            self.numbers.remove(&node);
+        }
+    }
+
+    /// Drops nodes the kernel doesn't reference
+    fn expire(&mut self) {
+        self.nodes.retain(|_, (_, lookups)| *lookups > 0);
+        self.numbers
+            .retain(|_, number| self.nodes.contains_key(number));
+        debug!("{} inodes left after expiring", self.nodes.len());
+    }
+}
+
+struct State {
+    db: Database,
+    roots: Vec<PathBuf>,
+    inodes: Inodes,
+}
+
+/// `/tags/<tag>/<tag2>/` directories with symlinks to files having all of the tags.
+/// `mkdir` creates tags, removing a link untags the file from the last tag of its directory
+struct TagsFilesystem {
+    state: Mutex<State>,
+    uid: u32,
+    gid: u32,
+    mounted_at: SystemTime,
+}
+
+impl TagsFilesystem {
+    /// Files and directories are owned by the owner of the database root
+    fn new(db: Database, roots: Vec<PathBuf>) -> Result<Self, AppError> {
+        let metadata = std::fs::metadata(&roots[0])?;
+
+        Ok(Self {
+            state: Mutex::new(State {
+                db,
+                roots,
+                inodes: Inodes::new(),
+            }),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            mounted_at: SystemTime::now(),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // Filesystem is still usable after a panicked request
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn attr(&self, ino: INodeNo, node: &Node) -> FileAttr {
+        let (kind, perm, size) = match node {
+            Node::Link { target, .. } => {
+                (FileType::Symlink, 0o777, target.as_os_str().len() as u64)
+            }
+            _ => (FileType::Directory, 0o755, 0),
+        };
+
+        FileAttr {
+            ino,
+            size,
+            blocks: 0,
+            atime: self.mounted_at,
+            mtime: self.mounted_at,
+            ctime: self.mounted_at,
+            crtime: self.mounted_at,
+            kind,
+            perm,
+            nlink: if kind == FileType::Directory { 2 } else { 1 },
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        }
+    }
+
+    /// Looks up child of the directory and replies with its attributes
+    fn reply_child(&self, state: &mut State, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        let Some(parent) = state.inodes.get(parent).cloned() else {
+            return reply.error(Errno::ENOENT);
+        };
+        let children = match children(state, &parent) {
+            Ok(children) => children,
+            Err(err) => return reply.error(errno(&err)),
+        };
+        let Some((_, node)) = children
+            .into_iter()
+            .find(|(child, _)| name == child.as_str())
+        else {
+            return reply.error(Errno::ENOENT);
+        };
+
+        let ino = state.inodes.lookup(node.clone());
+        reply.entry(&TTL, &self.attr(ino, &node), Generation(0));
+    }
+}
+
+impl Filesystem for TagsFilesystem {
+    fn lookup(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        let mut state = self.state();
+        self.reply_child(&mut state, parent, name, reply);
+    }
+
+    fn forget(&self, _req: &Request, ino: INodeNo, nlookup: u64) {
+        self.state().inodes.forget(ino, nlookup);
+    }
+
+    fn getattr(&self, _req: &Request, ino: INodeNo, _fh: Option<FileHandle>, reply: ReplyAttr) {
+        let state = self.state();
+        match state.inodes.get(ino) {
+            Some(node) => reply.attr(&TTL, &self.attr(ino, node)),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn readlink(&self, _req: &Request, ino: INodeNo, reply: ReplyData) {
+        let state = self.state();
+        match state.inodes.get(ino) {
+            Some(Node::Link { target, .. }) => reply.data(target.as_os_str().as_encoded_bytes()),
+            Some(_) => reply.error(Errno::EINVAL),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn readdir(
+        &self,
+        _req: &Request,
+        ino: INodeNo,
+        _fh: FileHandle,
+        offset: u64,
+        mut reply: ReplyDirectory,
+    ) {
+        let mut state = self.state();
+        let Some(node) = state.inodes.get(ino).cloned() else {
+            return reply.error(Errno::ENOENT);
+        };
+        let children = match children(&state, &node) {
+            Ok(children) => children,
+            Err(err) => return reply.error(errno(&err)),
+        };
+
+        let mut entries = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (ino, FileType::Directory, "..".to_string()),
+        ];
+        for (name, child) in children {
+            let kind = match child {
+                Node::Link { .. } => FileType::Symlink,
+                _ => FileType::Directory,
+            };
+            entries.push((state.inodes.number(child), kind, name));
+        }
+        for (index, (ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(ino, index as u64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn mkdir(
+        &self,
+        _req: &Request,
+        parent: INodeNo,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        reply: ReplyEntry,
+    ) {
+        let mut state = self.state();
+        let Some(query) = (match state.inodes.get(parent) {
+            Some(Node::Tags) => Some(Vec::new()),
+            Some(Node::Query(query)) => Some(query.clone()),
+            _ => None,
+        }) else {
+            return reply.error(Errno::EPERM);
+        };
+        let Some(tag) = name.to_str() else {
+            return reply.error(Errno::EINVAL);
+        };
+        if query.iter().any(|query_tag| query_tag == tag)
+            || state
+                .db
+                .get_all_tags()
+                .is_ok_and(|tags| tags.iter().any(|t| t == tag))
+        {
+            return reply.error(Errno::EEXIST);
+        }
+
+        debug!("mkdir creates tag {tag}");
+        if let Err(err) = state.db.create_tag(tag) {
+            return reply.error(errno(&err));
+        }
+        // New tag has no files yet, so it's only listed directly under `tags`
+        let mut child_query = query;
+        child_query.push(tag.to_string());
+        let node = Node::Query(child_query);
+        let ino = state.inodes.lookup(node.clone());
+        reply.entry(&TTL, &self.attr(ino, &node), Generation(0));
+    }
+
+    fn unlink(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEmpty) {
+        let mut state = self.state();
+        let Some(Node::Query(query)) = state.inodes.get(parent).cloned() else {
+            return reply.error(Errno::EPERM);
+        };
+        let link = children(&state, &Node::Query(query.clone())).map(|children| {
+            children
+                .into_iter()
+                .find(|(child, node)| name == child.as_str() && matches!(node, Node::Link { .. }))
+        });
+        let (database, target) = match link {
+            Ok(Some((
+                _,
+                Node::Link {
+                    database, target, ..
+                },
+            ))) => (database, target),
+            Ok(_) => return reply.error(Errno::ENOENT),
+            Err(err) => return reply.error(errno(&err)),
+        };
+        // Attached databases are opened only for reading
+        if database != 0 {
+            return reply.error(Errno::EROFS);
+        }
+
+        let Some(tag) = query.last() else {
+            return reply.error(Errno::EPERM);
+        };
+        debug!("unlink untags {} from {tag}", target.display());
+        let State { db, roots, .. } = &mut *state;
+        match super::untag::untag_file(db, &roots[0], &target, &[tag]) {
+            Ok(()) => reply.ok(),
+            Err(err) => {
+                warn!("could not untag {}: {err}", target.display());
+                reply.error(Errno::EIO);
+            }
+        }
+    }
+}
+
+/// Directory entries of the node, names of links are made unique like `photo (2).jpg`.
+/// Query directories only list tags of files they contain
+fn children(state: &State, node: &Node) -> Result<Vec<(String, Node)>, DatabaseError> {
+    let query = match node {
+        Node::Root => return Ok(vec![(TAGS_DIRECTORY.into(), Node::Tags)]),
+        Node::Link { .. } => return Ok(Vec::new()),
+        Node::Tags => Vec::new(),
+        Node::Query(query) => query.clone(),
+    };
+
+    let query_tags: Vec<&str> = query.iter().map(String::as_str).collect();
+    let tags = if query.is_empty() {
+        state.db.get_all_tags()?
+    } else {
+        let co_occurring = state.db.get_tags_of_files_by_tags(&query_tags)?;
+        state
+            .db
+            .get_all_tags()?
+            .into_iter()
+            .filter(|tag| co_occurring.contains(tag))
+            .collect()
+    };
+    let mut children: Vec<(String, Node)> = tags
+        .into_iter()
+        // Tags with slashes could not be directory names
+        .filter(|tag| !tag.contains('/') && !query.contains(tag))
+        .map(|tag| {
+            let mut child_query = query.clone();
+            child_query.push(tag.clone());
+            (tag, Node::Query(child_query))
+        })
+        .collect();
+    if query.is_empty() {
+        return Ok(children);
+    }
+
+    let mut files: Vec<_> = state
+        .db
+        .get_files_by_tags(&query_tags)?
+        .into_iter()
+        .map(|stored_path| {
+            let target = state.roots[stored_path.database].join(stored_path.path);
+            (stored_path.database, target)
+        })
+        .collect();
+    files.sort_by(|(_, a), (_, b)| a.cmp(b));
+    for (database, target) in files {
+        let name = unique_name(&children, &target);
+        children.push((
+            name.clone(),
+            Node::Link {
+                query: query.clone(),
+                name,
+                database,
+                target,
+            },
+        ));
+    }
+
+    Ok(children)
+}
+
+fn unique_name(children: &[(String, Node)], target: &Path) -> String {
+    let file_name = Path::new(target.file_name().unwrap_or_default());
+    let stem = file_name
+        .file_stem()
+        .unwrap_or_default()
+        .display()
+        .to_string();
+    let extension = file_name
+        .extension()
+        .map(|extension| format!(".{}", extension.display()))
+        .unwrap_or_default();
+
+    let mut name = file_name.display().to_string();
+    let mut index = 1;
+    while children.iter().any(|(child, _)| *child == name) {
+        index += 1;
+        name = format!("{stem} ({index}){extension}");
+    }
+
+    name
+}
+
+fn errno(err: &DatabaseError) -> Errno {
+    warn!("database error: {err}");
+    match err {
+        DatabaseError::ReservedTagName(_) => Errno::EINVAL,
+        DatabaseError::ReadOnly => Errno::EROFS,
+        _ => Errno::EIO,
+    }
+}
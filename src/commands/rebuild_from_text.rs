@@ -0,0 +1,87 @@
+use crate::{
+    AppError,
+    db::{Database, ImportMode, TaggedFile, parse_text_sidecar},
+};
+use std::{collections::BTreeSet, path::Path};
+use tracing::debug;
+
+/// Replaces database contents with files and tags from text sidecar.
+/// Files are hashed from disk, files missing on disk keep hashes stored in database
+pub fn rebuild_from_text(
+    db: &mut Database,
+    root: &Path,
+    text_path: &Path,
+) -> Result<Option<String>, AppError> {
+    let entries = parse_text_sidecar(&std::fs::read_to_string(text_path)?)?;
+    debug!("rebuilding from {} entries", entries.len());
+
+    let stored_files = db.get_tagged_files()?;
+    let mut missing = Vec::new();
+    let mut files = Vec::with_capacity(entries.len());
+    for (path, tags) in entries {
+        let file_path = root.join(&path);
+        let file = if file_path.is_file() {
+            super::prepare_file_arg(root, &file_path)?
+        } else if let Some(stored) = stored_files.iter().find(|stored| stored.file.path == path) {
+            stored.file.clone()
+        } else {
+            missing.push(path);
+            continue;
+        };
+        files.push(TaggedFile { file, tags });
+    }
+
+    let tags: Vec<String> = files
+        .iter()
+        .flat_map(|tagged_file| tagged_file.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    db.import_files(&tags, &files, ImportMode::Replace)?;
+
+    let mut lines: Vec<String> = missing
+        .iter()
+        .map(|path| format!("Missing: {path}"))
+        .collect();
+    lines.extend(
+        stored_files
+            .iter()
+            .filter(|stored| !files.iter().any(|file| file.file.path == stored.file.path))
+            .map(|stored| format!("Removed: {}", stored.file.path)),
+    );
+    let mut unchanged = 0;
+    for tagged_file in &files {
+        let Some(stored) = stored_files
+            .iter()
+            .find(|stored| stored.file.path == tagged_file.file.path)
+        else {
+            lines.push(format!("Added: {}", tagged_file.file.path));
+            continue;
+        };
+        let changes: Vec<String> = tagged_file
+            .tags
+            .iter()
+            .filter(|tag| !stored.tags.contains(tag))
+            .map(|tag| format!("+{tag}"))
+            .chain(
+                stored
+                    .tags
+                    .iter()
+                    .filter(|tag| !tagged_file.tags.contains(tag))
+                    .map(|tag| format!("-{tag}")),
+            )
+            .collect();
+        if changes.is_empty() {
+            unchanged += 1;
+        } else {
+            lines.push(format!(
+                "Updated: {} ({})",
+                tagged_file.file.path,
+                changes.join(",")
+            ));
+        }
+    }
+    lines.push(format!("Unchanged: {unchanged} files"));
+
+    Ok(Some(lines.join("\n")))
+}
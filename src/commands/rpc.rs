@@ -0,0 +1,216 @@
+use crate::{
+    AppError,
+    db::{Database, DatabaseError},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+use tracing::debug;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Errors of anytaggy itself, like missing files
+const APP_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    /// Notifications have no id and get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilesParams {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileParams {
+    path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TagParams {
+    path: PathBuf,
+    tags: Vec<String>,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl From<AppError> for RpcError {
+    fn from(err: AppError) -> Self {
+        let code = match err {
+            AppError::NoTagsSpecified | AppError::InvalidTagName(_) => INVALID_PARAMS,
+            _ => APP_ERROR,
+        };
+        Self {
+            code,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<DatabaseError> for RpcError {
+    fn from(err: DatabaseError) -> Self {
+        AppError::from(err).into()
+    }
+}
+
+/// Answers line-delimited JSON-RPC 2.0 requests and batches of them until input is closed,
+/// keeping database open. Methods are `tags`, `files {tags}`, `file {path}`,
+/// `tag {path, tags}` and `untag {path, tags}`, file paths are relative to database root
+pub fn rpc(
+    db: &mut Database,
+    roots: &[PathBuf],
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<Option<String>, AppError> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Array(requests)) => handle_batch(db, roots, requests),
+            Ok(request) => handle_request(db, roots, request),
+            Err(err) => Some(error_response(&Value::Null, PARSE_ERROR, &err.to_string())),
+        };
+        if let Some(response) = response {
+            debug!("response: {response}");
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Requests are answered in order with an array of responses, which is left out
+/// when all of them are notifications
+fn handle_batch(db: &mut Database, roots: &[PathBuf], requests: Vec<Value>) -> Option<Value> {
+    if requests.is_empty() {
+        return Some(error_response(&Value::Null, INVALID_REQUEST, "empty batch"));
+    }
+
+    let responses: Vec<Value> = requests
+        .into_iter()
+        .filter_map(|request| handle_request(db, roots, request))
+        .collect();
+    (!responses.is_empty()).then_some(Value::Array(responses))
+}
+
+fn handle_request(db: &mut Database, roots: &[PathBuf], request: Value) -> Option<Value> {
+    let request: RpcRequest = match serde_json::from_value(request) {
+        Ok(request) => request,
+        Err(err) => {
+            return Some(error_response(
+                &Value::Null,
+                INVALID_REQUEST,
+                &err.to_string(),
+            ));
+        }
+    };
+    if request.jsonrpc != "2.0" {
+        let id = request.id.unwrap_or_default();
+        return Some(error_response(
+            &id,
+            INVALID_REQUEST,
+            "jsonrpc must be \"2.0\"",
+        ));
+    }
+    debug!("request: {} {}", request.method, request.params);
+
+    let result = call(db, roots, &request.method, request.params);
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(err) => error_response(&id, err.code, &err.message),
+    })
+}
+
+fn call(
+    db: &mut Database,
+    roots: &[PathBuf],
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    let root = &roots[0];
+    match method {
+        "tags" => Ok(json!(db.get_all_tags()?)),
+        "files" => {
+            let params: FilesParams = parse_params(params)?;
+            let tags: Vec<&str> = params.tags.iter().map(String::as_str).collect();
+            let files = if tags.is_empty() {
+                db.get_files()?
+            } else {
+                db.get_files_by_tags(&tags)?
+            };
+            let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+            Ok(json!(paths))
+        }
+        "file" => {
+            let params: FileParams = parse_params(params)?;
+            let file_path = existing_file(root.join(params.path))?;
+            Ok(json!(super::file_details(db, root, &file_path)?))
+        }
+        "tag" => {
+            let params: TagParams = parse_params(params)?;
+            let file_path = existing_file(root.join(params.path))?;
+            let tags: Vec<&str> = params.tags.iter().map(String::as_str).collect();
+            super::check_tag_names(&tags)?;
+
+            super::tag::tag_file(db, root, &file_path, &tags)?;
+            Ok(json!(super::file_details(db, root, &file_path)?))
+        }
+        "untag" => {
+            let params: TagParams = parse_params(params)?;
+            let file_path = existing_file(root.join(params.path))?;
+            let tags: Vec<&str> = params.tags.iter().map(String::as_str).collect();
+            super::check_tag_names(&tags)?;
+
+            super::untag::untag_file(db, root, &file_path, &tags)?;
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method: {method}"),
+        }),
+    }
+}
+
+/// Missing params are the same as empty ones
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|err| RpcError {
+        code: INVALID_PARAMS,
+        message: err.to_string(),
+    })
+}
+
+fn existing_file(file_path: PathBuf) -> Result<PathBuf, RpcError> {
+    if file_path.is_file() {
+        Ok(file_path)
+    } else {
+        Err(AppError::FileNotFound.into())
+    }
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
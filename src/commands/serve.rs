@@ -0,0 +1,215 @@
+use crate::{
+    AppError,
+    db::{Database, DatabaseError},
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{debug, info, warn};
+
+/// Bodies are JSON arrays of tags, anything bigger is refused
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Serves JSON API until the process is stopped, requests are handled one at a time.
+/// Tagging and untagging are refused unless server is writable
+pub fn serve(
+    db: &mut Database,
+    roots: &[PathBuf],
+    listen: &str,
+    writable: bool,
+) -> Result<Option<String>, AppError> {
+    let server = Server::http(listen).map_err(|err| AppError::Serve(err.to_string()))?;
+    info!("listening on {listen}, writable: {writable}");
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = match handle_request(db, roots, listen, writable, &mut request) {
+            Ok(Some(body)) => (200, body),
+            Ok(None) => (204, String::new()),
+            Err(err) => (
+                status_code(&err),
+                to_json(&ErrorBody {
+                    error: err.to_string(),
+                })?,
+            ),
+        };
+        debug!("{} {}: {status}", request.method(), request.url());
+
+        let content_type =
+            Header::from_bytes("Content-Type", "application/json").expect("header is valid");
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(err) = request.respond(response) {
+            warn!("could not respond: {err}");
+        }
+    }
+
+    Ok(None)
+}
+
+fn handle_request(
+    db: &mut Database,
+    roots: &[PathBuf],
+    listen: &str,
+    writable: bool,
+    request: &mut Request,
+) -> Result<Option<String>, AppError> {
+    check_host(listen, header(request, "Host").unwrap_or_default())?;
+    let url = request.url().to_string();
+    let (endpoint, query) = url.split_once('?').unwrap_or((&url, ""));
+    let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let root = &roots[0];
+
+    match (request.method(), endpoint) {
+        (Method::Get, "/tags") => to_json(&db.get_all_tags()?).map(Some),
+        (Method::Get, "/files") => {
+            let tags = list_param(&params, "tags");
+            let files = if tags.is_empty() {
+                db.get_files()?
+            } else {
+                db.get_files_by_tags(&tags)?
+            };
+            let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+            to_json(&paths).map(Some)
+        }
+        (Method::Get, "/file") => {
+            let file_path = file_param(root, &params)?;
+            to_json(&super::file_details(db, root, &file_path)?).map(Some)
+        }
+        (Method::Post, "/file/tags") => {
+            if !writable {
+                return Err(AppError::ReadOnlyServer);
+            }
+            // Browsers can't send JSON to other origins without asking first
+            let content_type = header(request, "Content-Type").unwrap_or_default();
+            if !content_type.split(';').next().is_some_and(|media_type| {
+                media_type.trim().eq_ignore_ascii_case("application/json")
+            }) {
+                return Err(AppError::UnsupportedContentType);
+            }
+            let file_path = file_param(root, &params)?;
+            let mut body = String::new();
+            request
+                .as_reader()
+                .take(MAX_BODY_SIZE + 1)
+                .read_to_string(&mut body)?;
+            if body.len() as u64 > MAX_BODY_SIZE {
+                return Err(AppError::RequestTooLarge(MAX_BODY_SIZE));
+            }
+            let tags: Vec<String> = serde_json::from_str(&body)
+                .map_err(|err| AppError::InvalidRequest(err.to_string()))?;
+            let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+            super::check_tag_names(&tags)?;
+            super::tag::tag_file(db, root, &file_path, &tags)?;
+            to_json(&super::file_details(db, root, &file_path)?).map(Some)
+        }
+        (Method::Delete, "/file/tags") => {
+            if !writable {
+                return Err(AppError::ReadOnlyServer);
+            }
+            let file_path = file_param(root, &params)?;
+            let tags = list_param(&params, "tags");
+            super::check_tag_names(&tags)?;
+
+            super::untag::untag_file(db, root, &file_path, &tags)?;
+            Ok(None)
+        }
+        _ => Err(AppError::UnknownEndpoint(format!(
+            "{} {endpoint}",
+            request.method()
+        ))),
+    }
+}
+
+/// Comma separated values of the parameter, like `tags=photo,holiday`.
+/// Blank values are kept, so they could be refused
+fn list_param<'a>(params: &'a HashMap<String, String>, name: &str) -> Vec<&'a str> {
+    params
+        .get(name)
+        .filter(|values| !values.trim().is_empty())
+        .map_or_else(Vec::new, |values| {
+            values.split(',').map(str::trim).collect()
+        })
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Only names of this machine are accepted, so other sites could not reach the server
+/// through a domain resolving to it
+fn check_host(listen: &str, host: &str) -> Result<(), AppError> {
+    let requested = host_name(host);
+    let is_allowed = ["localhost", "127.0.0.1", "[::1]", host_name(listen)]
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(requested));
+    if !is_allowed {
+        return Err(AppError::HostNotAllowed(host.into()));
+    }
+
+    Ok(())
+}
+
+/// Address without its port, like `localhost` or `[::1]`
+fn host_name(address: &str) -> &str {
+    match address.find(']') {
+        Some(end) if address.starts_with('[') => &address[..=end],
+        _ => address.split(':').next().unwrap_or_default(),
+    }
+}
+
+/// File from `path` parameter, relative paths are resolved against database root
+fn file_param(root: &Path, params: &HashMap<String, String>) -> Result<PathBuf, AppError> {
+    let Some(path) = params.get("path") else {
+        return Err(AppError::InvalidRequest("missing 'path' parameter".into()));
+    };
+    let file_path = root.join(path);
+    if !file_path.is_file() {
+        return Err(AppError::FileNotFound);
+    }
+
+    Ok(file_path)
+}
+
+fn status_code(err: &AppError) -> u16 {
+    match err {
+        AppError::FileNotFound
+        | AppError::UnknownEndpoint(_)
+        | AppError::Database(
+            DatabaseError::NoSuchFile
+            | DatabaseError::NoSuchTag(_)
+            | DatabaseError::NoSuchTagOnFile(_),
+        ) => 404,
+        AppError::InvalidRequest(_)
+        | AppError::NoTagsSpecified
+        | AppError::InvalidTagName(_)
+        | AppError::FileOutsideStructure
+        | AppError::Database(DatabaseError::ReservedTagName(_)) => 400,
+        AppError::ReadOnlyServer
+        | AppError::HostNotAllowed(_)
+        | AppError::Database(DatabaseError::ReadOnly) => 403,
+        AppError::RequestTooLarge(_) => 413,
+        AppError::UnsupportedContentType => 415,
+        AppError::Database(DatabaseError::Locked) => 503,
+        _ => 500,
+    }
+}
+
+fn to_json(value: &impl Serialize) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(|err| AppError::SerializeResponse(err.to_string()))
+}
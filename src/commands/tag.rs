@@ -1,10 +1,33 @@
-use crate::{AppError, db::Database};
+use crate::{
+    AppError,
+    db::{Database, Setting},
+};
 use std::path::Path;
 
-pub fn tag_file(db: &mut Database, file_path: &Path, tag_names: &[&str]) -> Result<(), AppError> {
-    let file = super::prepare_file_arg(file_path)?;
+pub fn tag_file(
+    db: &mut Database,
+    root: &Path,
+    file_path: &Path,
+    tag_names: &[&str],
+) -> Result<(), AppError> {
+    let file = super::prepare_file_arg(root, file_path)?;
 
-    db.tag_file(&file, tag_names)?;
+    let mut tag_names = tag_names.to_vec();
+    let rule_matches = if db.get_setting(Setting::Autotag)? {
+        super::autotag::Rules::load(root)?.matches(Path::new(&file.path), file_path)?
+    } else {
+        Vec::new()
+    };
+    for rule_match in &rule_matches {
+        if !tag_names.contains(&rule_match.tag.as_str()) {
+            tag_names.push(&rule_match.tag);
+        }
+    }
+
+    db.tag_file(&file, &tag_names)?;
+    if db.get_setting(Setting::XattrSync)? {
+        super::xattr::sync_file(db, file_path, &file.fingerprint_hash);
+    }
 
     Ok(())
 }
@@ -1,25 +1,52 @@
 use crate::{AppError, db::Database};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::debug;
 
-pub fn get_file_tags(db: &Database, file_path: &Path) -> Result<Option<String>, AppError> {
+pub fn get_file_tags(
+    db: &Database,
+    roots: &[PathBuf],
+    file_path: &Path,
+) -> Result<Option<String>, AppError> {
+    let file_tags = file_tags(db, roots, file_path)?;
+    if file_tags.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(file_tags.join(",").to_string()))
+    }
+}
+
+/// Tags of the file from every database it's located under
+pub(super) fn file_tags(
+    db: &Database,
+    roots: &[PathBuf],
+    file_path: &Path,
+) -> Result<Vec<String>, AppError> {
     debug!("file_path: {}", file_path.display());
 
     let contents_hash = super::get_file_contents_hash(file_path)?;
     debug!("contents_hash: {contents_hash}");
 
-    let fingerprint_hash =
-        super::get_fingerprint_hash(&contents_hash, &file_path.display().to_string())?;
-    debug!("fingerprint_hash: {fingerprint_hash}");
+    let mut file_tags: Vec<String> = Vec::new();
+    // File is looked up in every database it's located under
+    for (database, root) in roots.iter().enumerate() {
+        let Ok(path) = super::relative_file_path(root, file_path) else {
+            continue;
+        };
+        debug!("path: {}", path.display());
 
-    let file_tags = db.get_file_tags_by_hash(&fingerprint_hash)?;
-    debug!("file_tags: {file_tags:?}");
+        let fingerprint_hash =
+            super::get_fingerprint_hash(&contents_hash, &path.display().to_string())?;
+        debug!("fingerprint_hash: {fingerprint_hash}");
 
-    if file_tags.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(file_tags.join(",").to_string()))
+        for tag in db.get_file_tags_by_hash(database, &fingerprint_hash)? {
+            if !file_tags.contains(&tag) {
+                file_tags.push(tag);
+            }
+        }
     }
+    debug!("file_tags: {file_tags:?}");
+
+    Ok(file_tags)
 }
 
 pub fn get_all_tags(db: &Database) -> Result<Option<String>, AppError> {
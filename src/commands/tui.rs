@@ -0,0 +1,497 @@
+use crate::{
+    AppError,
+    db::{Database, StoredPath},
+};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+};
+use std::{
+    collections::BTreeSet,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+const HELP: &str = "/ query  Tab switch pane  Enter add tag to query  Space select  a add tags  d remove tags  q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Tags,
+    Files,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// Query is being typed, files are updated on every key
+    Query,
+    /// Comma separated tags are being typed, to be added to or removed from files
+    AddTags(String),
+    RemoveTags(String),
+}
+
+struct App<'a> {
+    db: &'a mut Database,
+    roots: &'a [PathBuf],
+    /// Space separated tags, like in 'files'
+    query: String,
+    tags: Vec<(String, usize)>,
+    files: Vec<StoredPath>,
+    /// Selected files stay selected when query changes
+    selected: BTreeSet<PathBuf>,
+    focus: Pane,
+    mode: Mode,
+    tags_state: ListState,
+    files_state: ListState,
+    status: String,
+}
+
+/// Two-pane terminal interface: tags with counts and files matching the query,
+/// files could be selected to add or remove tags on all of them at once
+pub fn tui(db: &mut Database, roots: &[PathBuf]) -> Result<Option<String>, AppError> {
+    if !std::io::stdout().is_terminal() {
+        return Err(AppError::NotATerminal);
+    }
+
+    let mut app = App::new(db, roots)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    result.map(|()| None)
+}
+
+impl<'a> App<'a> {
+    fn new(db: &'a mut Database, roots: &'a [PathBuf]) -> Result<Self, AppError> {
+        let mut app = App {
+            db,
+            roots,
+            query: String::new(),
+            tags: Vec::new(),
+            files: Vec::new(),
+            selected: BTreeSet::new(),
+            focus: Pane::Files,
+            mode: Mode::Browse,
+            tags_state: ListState::default(),
+            files_state: ListState::default(),
+            status: HELP.into(),
+        };
+        app.refresh()?;
+
+        Ok(app)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), AppError> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key)?
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns `false` when the interface should be closed
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool, AppError> {
+        match (&mut self.mode, key.code) {
+            (Mode::Browse, KeyCode::Char('q') | KeyCode::Esc) => return Ok(false),
+            (Mode::Browse, KeyCode::Char('/')) => self.mode = Mode::Query,
+            (Mode::Browse, KeyCode::Tab) => {
+                self.focus = match self.focus {
+                    Pane::Tags => Pane::Files,
+                    Pane::Files => Pane::Tags,
+                };
+            }
+            (Mode::Browse, KeyCode::Down | KeyCode::Char('j')) => {
+                self.focused_state().select_next()
+            }
+            (Mode::Browse, KeyCode::Up | KeyCode::Char('k')) => {
+                self.focused_state().select_previous();
+            }
+            (Mode::Browse, KeyCode::Enter) if self.focus == Pane::Tags => {
+                if let Some((tag, _)) = self.tags_state.selected().and_then(|i| self.tags.get(i)) {
+                    let tag = tag.clone();
+                    if !self.query_tags().contains(&tag.as_str()) {
+                        self.query = format!("{} {tag}", self.query.trim());
+                        self.refresh()?;
+                    }
+                }
+            }
+            (Mode::Browse, KeyCode::Char(' ')) if self.focus == Pane::Files => {
+                if let Some(path) = self.highlighted_file() {
+                    if !self.selected.remove(&path) {
+                        self.selected.insert(path);
+                    }
+                    self.files_state.select_next();
+                }
+            }
+            (Mode::Browse, KeyCode::Char('a')) => self.mode = Mode::AddTags(String::new()),
+            (Mode::Browse, KeyCode::Char('d')) => self.mode = Mode::RemoveTags(String::new()),
+            (Mode::Query, KeyCode::Enter | KeyCode::Esc) => self.mode = Mode::Browse,
+            (Mode::Query, KeyCode::Char(character)) => {
+                self.query.push(character);
+                self.refresh()?;
+            }
+            (Mode::Query, KeyCode::Backspace) => {
+                self.query.pop();
+                self.refresh()?;
+            }
+            (Mode::AddTags(_) | Mode::RemoveTags(_), KeyCode::Esc) => {
+                self.mode = Mode::Browse;
+                self.status = HELP.into();
+            }
+            (Mode::AddTags(input) | Mode::RemoveTags(input), KeyCode::Char(character)) => {
+                input.push(character);
+            }
+            (Mode::AddTags(input) | Mode::RemoveTags(input), KeyCode::Backspace) => {
+                input.pop();
+            }
+            (Mode::AddTags(_) | Mode::RemoveTags(_), KeyCode::Enter) => {
+                let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                self.apply(&mode)?;
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    fn focused_state(&mut self) -> &mut ListState {
+        match self.focus {
+            Pane::Tags => &mut self.tags_state,
+            Pane::Files => &mut self.files_state,
+        }
+    }
+
+    /// Tags of the query without duplicates
+    fn query_tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = Vec::new();
+        for tag in self.query.split_whitespace() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    fn highlighted_file(&self) -> Option<PathBuf> {
+        let file = self.files.get(self.files_state.selected()?)?;
+        Some(self.roots[file.database].join(&file.path))
+    }
+
+    /// Selected files, or the highlighted one when nothing is selected
+    fn target_files(&self) -> Vec<PathBuf> {
+        if self.selected.is_empty() {
+            self.highlighted_file().into_iter().collect()
+        } else {
+            self.selected.iter().cloned().collect()
+        }
+    }
+
+    fn refresh(&mut self) -> Result<(), AppError> {
+        self.tags = self.db.get_tag_counts()?;
+        let query_tags = self.query_tags();
+        self.files = if query_tags.is_empty() {
+            self.db.get_files()?
+        } else {
+            self.db.get_files_by_tags(&query_tags)?
+        };
+        debug!("query {:?} matched {} files", self.query, self.files.len());
+
+        for (state, len) in [
+            (&mut self.tags_state, self.tags.len()),
+            (&mut self.files_state, self.files.len()),
+        ] {
+            if len == 0 {
+                state.select(None);
+            } else if state.selected().is_none_or(|selected| selected >= len) {
+                state.select(Some(0));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds or removes tags on target files, failures are counted in status
+    fn apply(&mut self, mode: &Mode) -> Result<(), AppError> {
+        let (input, adding) = match mode {
+            Mode::AddTags(input) => (input, true),
+            Mode::RemoveTags(input) => (input, false),
+            _ => return Ok(()),
+        };
+        let tags: Vec<&str> = input
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        let files = self.target_files();
+        if tags.is_empty() || files.is_empty() {
+            self.status = "Nothing to change".into();
+            return Ok(());
+        }
+
+        let root = &self.roots[0];
+        let mut errors = Vec::new();
+        for file in &files {
+            let result = if !file.starts_with(root) || !file.is_file() {
+                Err(AppError::FileOutsideStructure)
+            } else if adding {
+                super::tag::tag_file(self.db, root, file, &tags)
+            } else {
+                super::untag::untag_file(self.db, root, file, &tags)
+            };
+            if let Err(err) = result {
+                errors.push(format!("{}: {err}", display_path(root, file)));
+            }
+        }
+
+        let action = if adding { "Tagged" } else { "Untagged" };
+        self.status = format!("{action} {} files", files.len() - errors.len());
+        if let Some(error) = errors.first() {
+            self.status = format!("{}, {} failed, {error}", self.status, errors.len());
+        }
+        self.selected.clear();
+        self.refresh()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [query_area, main_area, status_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [tags_area, files_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main_area);
+
+        let query_block = Block::bordered().title("Query");
+        let query_block = if self.mode == Mode::Query {
+            query_block.yellow()
+        } else {
+            query_block
+        };
+        frame.render_widget(
+            Paragraph::new(self.query.as_str()).block(query_block),
+            query_area,
+        );
+
+        let tags: Vec<ListItem> = self
+            .tags
+            .iter()
+            .map(|(tag, count)| ListItem::new(format!("{tag} ({count})")))
+            .collect();
+        self.render_list(frame, tags_area, Pane::Tags, tags);
+
+        let root = &self.roots[0];
+        let files: Vec<ListItem> = self
+            .files
+            .iter()
+            .map(|file| {
+                let path = self.roots[file.database].join(&file.path);
+                let mark = if self.selected.contains(&path) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                ListItem::new(format!("{mark} {}", display_path(root, &path)))
+            })
+            .collect();
+        self.render_list(frame, files_area, Pane::Files, files);
+
+        let status = match &self.mode {
+            Mode::AddTags(input) => {
+                format!("Add tags to {} files: {input}", self.target_files().len())
+            }
+            Mode::RemoveTags(input) => {
+                format!(
+                    "Remove tags from {} files: {input}",
+                    self.target_files().len()
+                )
+            }
+            _ => self.status.clone(),
+        };
+        let status = Line::from(status);
+        let status_width = status.width() as u16;
+        frame.render_widget(status, status_area);
+
+        match &self.mode {
+            Mode::Query => frame.set_cursor_position((
+                query_area.x + 1 + self.query.chars().count() as u16,
+                query_area.y + 1,
+            )),
+            Mode::AddTags(_) | Mode::RemoveTags(_) => {
+                frame.set_cursor_position((status_area.x + status_width, status_area.y))
+            }
+            Mode::Browse => {}
+        }
+    }
+
+    fn render_list(&mut self, frame: &mut Frame, area: Rect, pane: Pane, items: Vec<ListItem>) {
+        let title = match pane {
+            Pane::Tags => format!("Tags ({})", self.tags.len()),
+            Pane::Files => format!(
+                "Files ({}, {} selected)",
+                self.files.len(),
+                self.selected.len()
+            ),
+        };
+        let block = Block::bordered().title(title);
+        let block = if self.focus == pane {
+            block.cyan()
+        } else {
+            block
+        };
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        let state = match pane {
+            Pane::Tags => &mut self.tags_state,
+            Pane::Files => &mut self.files_state,
+        };
+        frame.render_stateful_widget(list, area, state);
+    }
+}
+
+/// Files of the main database are shown relative to its root
+fn display_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::App;
+    use crate::db::{Database, DatabaseMode};
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+    use std::path::{Path, PathBuf};
+    use temp_dir::TempDir;
+
+    /// Database with two files, tagged `test,test2,test3` and `test3,test4,test5`
+    fn prepare() -> (TempDir, Database, Vec<PathBuf>) {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let mut db =
+            Database::new(&DatabaseMode::ReadWriteCreate, &root.join("tmp_db.db")).unwrap();
+        for (name, tags) in [
+            ("temp_tag_file_1", ["test", "test2", "test3"]),
+            ("temp_tag_file_2", ["test3", "test4", "test5"]),
+        ] {
+            let file_path = root.join(name);
+            std::fs::write(&file_path, name).unwrap();
+            super::super::tag::tag_file(&mut db, &root, &file_path, &tags).unwrap();
+        }
+
+        (temp_dir, db, vec![root])
+    }
+
+    /// Feeds keys to the interface until one of them closes it, returns the last status line
+    fn handle_keys(db: &mut Database, roots: &[PathBuf], keys: &[KeyCode]) -> String {
+        let mut app = App::new(db, roots).unwrap();
+        for key in keys {
+            if !app.handle_key(KeyEvent::from(*key)).unwrap() {
+                break;
+            }
+        }
+        app.status
+    }
+
+    fn typed(text: &str) -> Vec<KeyCode> {
+        text.chars().map(KeyCode::Char).collect()
+    }
+
+    fn file_tags(db: &Database, root: &Path, name: &str) -> Vec<String> {
+        let file = crate::commands::prepare_file_arg(root, &root.join(name)).unwrap();
+        db.get_file_tags_by_hash(0, &file.fingerprint_hash).unwrap()
+    }
+
+    #[test]
+    fn adds_tags_to_selected_files() {
+        let (_temp_dir, mut db, roots) = prepare();
+
+        let mut keys = vec![KeyCode::Char(' '), KeyCode::Char(' '), KeyCode::Char('a')];
+        keys.extend(typed("new, other ,"));
+        keys.push(KeyCode::Enter);
+        assert_eq!("Tagged 2 files", handle_keys(&mut db, &roots, &keys));
+
+        assert_eq!(
+            vec!["test", "test2", "test3", "new", "other"],
+            file_tags(&db, &roots[0], "temp_tag_file_1")
+        );
+        assert_eq!(
+            vec!["test3", "test4", "test5", "new", "other"],
+            file_tags(&db, &roots[0], "temp_tag_file_2")
+        );
+    }
+
+    #[test]
+    fn removes_tags_from_highlighted_file_of_query() {
+        let (_temp_dir, mut db, roots) = prepare();
+
+        let mut keys = vec![KeyCode::Char('/')];
+        keys.extend(typed("test4"));
+        keys.extend([KeyCode::Enter, KeyCode::Char('d')]);
+        keys.extend(typed("test3"));
+        keys.push(KeyCode::Enter);
+        assert_eq!("Untagged 1 files", handle_keys(&mut db, &roots, &keys));
+
+        assert_eq!(
+            vec!["test", "test2", "test3"],
+            file_tags(&db, &roots[0], "temp_tag_file_1")
+        );
+        assert_eq!(
+            vec!["test4", "test5"],
+            file_tags(&db, &roots[0], "temp_tag_file_2")
+        );
+    }
+
+    #[test]
+    fn queries_highlighted_tag() {
+        let (_temp_dir, mut db, roots) = prepare();
+
+        // Tags are sorted by name, second one is 'test2' of the first file only
+        let mut keys = vec![
+            KeyCode::Tab,
+            KeyCode::Down,
+            KeyCode::Enter,
+            KeyCode::Tab,
+            KeyCode::Char('a'),
+        ];
+        keys.extend(typed("queried"));
+        keys.push(KeyCode::Enter);
+        assert_eq!("Tagged 1 files", handle_keys(&mut db, &roots, &keys));
+
+        assert_eq!(
+            vec!["test", "test2", "test3", "queried"],
+            file_tags(&db, &roots[0], "temp_tag_file_1")
+        );
+        assert_eq!(
+            vec!["test3", "test4", "test5"],
+            file_tags(&db, &roots[0], "temp_tag_file_2")
+        );
+    }
+
+    #[test]
+    fn cancels_tag_input() {
+        let (_temp_dir, mut db, roots) = prepare();
+
+        let mut keys = vec![KeyCode::Char('a')];
+        keys.extend(typed("cancelled"));
+        keys.extend([KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('a')]);
+        handle_keys(&mut db, &roots, &keys);
+
+        assert_eq!(
+            vec!["test", "test2", "test3"],
+            file_tags(&db, &roots[0], "temp_tag_file_1")
+        );
+    }
+}
@@ -1,9 +1,20 @@
-use crate::{AppError, db::Database};
+use crate::{
+    AppError,
+    db::{Database, Setting},
+};
 use std::path::Path;
 
-pub fn untag_file(db: &mut Database, file_path: &Path, tag_names: &[&str]) -> Result<(), AppError> {
-    let file = super::prepare_file_arg(file_path)?;
+pub fn untag_file(
+    db: &mut Database,
+    root: &Path,
+    file_path: &Path,
+    tag_names: &[&str],
+) -> Result<(), AppError> {
+    let file = super::prepare_file_arg(root, file_path)?;
     db.untag_file(&file, tag_names)?;
+    if db.get_setting(Setting::XattrSync)? {
+        super::xattr::sync_file(db, file_path, &file.fingerprint_hash);
+    }
 
     Ok(())
 }
@@ -0,0 +1,136 @@
+use crate::{AppError, db::Database};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// State of a view, stored inside of its directory
+pub const VIEW_STATE_FILENAME: &str = ".anytaggy-view";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ViewState {
+    /// Database the view was built from, used when database is not specified for sync
+    database: PathBuf,
+    query: Vec<String>,
+}
+
+/// Creates directory of symlinks to files matching the query.
+/// Existing directory has to be empty or contain a view already
+pub fn build(
+    db: &Database,
+    database_path: &Path,
+    roots: &[PathBuf],
+    directory: &Path,
+    query: &[String],
+) -> Result<Option<String>, AppError> {
+    if directory.is_dir()
+        && !directory.join(VIEW_STATE_FILENAME).is_file()
+        && std::fs::read_dir(directory)?.next().is_some()
+    {
+        return Err(AppError::ViewDirectoryNotEmpty(directory.to_path_buf()));
+    }
+    std::fs::create_dir_all(directory)?;
+
+    let state = ViewState {
+        database: database_path.canonicalize()?,
+        query: query.to_vec(),
+    };
+    let contents = toml::to_string(&state).map_err(|err| AppError::InvalidView(err.to_string()))?;
+    std::fs::write(directory.join(VIEW_STATE_FILENAME), contents)?;
+
+    sync_links(db, roots, directory, query)
+}
+
+/// Updates symlinks of the view to match its query again
+pub fn sync(
+    db: &Database,
+    roots: &[PathBuf],
+    directory: &Path,
+) -> Result<Option<String>, AppError> {
+    let state = read_state(directory)?;
+    sync_links(db, roots, directory, &state.query)
+}
+
+/// Database the view was built from
+pub fn view_database(directory: &Path) -> Result<PathBuf, AppError> {
+    Ok(read_state(directory)?.database)
+}
+
+fn read_state(directory: &Path) -> Result<ViewState, AppError> {
+    let contents = match std::fs::read_to_string(directory.join(VIEW_STATE_FILENAME)) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(AppError::NotAView(directory.to_path_buf()));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    toml::from_str(&contents).map_err(|err| AppError::InvalidView(err.message().into()))
+}
+
+/// Adds links to new matches and removes stale ones, other files are left untouched,
+/// as well as links pointing outside of database roots, which could only be added by user.
+/// Links are named after files, duplicate names get a number, e.g. `photo (2).jpg`
+fn sync_links(
+    db: &Database,
+    roots: &[PathBuf],
+    directory: &Path,
+    query: &[String],
+) -> Result<Option<String>, AppError> {
+    let mut targets: BTreeSet<PathBuf> = db
+        .get_files_by_tags(&query.iter().map(String::as_str).collect::<Vec<_>>())?
+        .into_iter()
+        .map(|stored_path| roots[stored_path.database].join(stored_path.path))
+        .collect();
+    debug!("view targets: {}", targets.len());
+
+    let mut lines = Vec::new();
+    let mut unchanged = 0;
+    let mut entries = std::fs::read_dir(directory)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    for entry in entries {
+        if !entry.file_type()?.is_symlink() {
+            continue;
+        }
+        let target = std::fs::read_link(entry.path())?;
+        if targets.remove(&target) {
+            unchanged += 1;
+        } else if roots.iter().any(|root| target.starts_with(root)) {
+            std::fs::remove_file(entry.path())?;
+            lines.push(format!("Unlinked: {}", entry.file_name().display()));
+        }
+    }
+
+    for target in targets {
+        let link_path = free_link_path(directory, &target);
+        std::os::unix::fs::symlink(&target, &link_path)?;
+        lines.push(format!(
+            "Linked: {}",
+            link_path.file_name().unwrap_or_default().display()
+        ));
+    }
+    lines.push(format!("Unchanged: {unchanged} links"));
+
+    Ok(Some(lines.join("\n")))
+}
+
+fn free_link_path(directory: &Path, target: &Path) -> PathBuf {
+    let name = Path::new(target.file_name().unwrap_or_default());
+    let mut link_path = directory.join(name);
+    let stem = name.file_stem().unwrap_or_default().display().to_string();
+    let extension = name
+        .extension()
+        .map(|extension| format!(".{}", extension.display()))
+        .unwrap_or_default();
+    let mut index = 1;
+    // Broken links still take the name
+    while link_path.symlink_metadata().is_ok() {
+        index += 1;
+        link_path = directory.join(format!("{stem} ({index}){extension}"));
+    }
+
+    link_path
+}
@@ -0,0 +1,148 @@
+use crate::{AppError, db::Database};
+use notify::{
+    Event, EventKind, RecursiveMode, Watcher,
+    event::{AccessKind, AccessMode, CreateKind, ModifyKind, RenameMode},
+};
+use std::{
+    io::Write,
+    path::Path,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+/// Follows changes under database root and keeps stored files in sync with them.
+/// Moved files keep their tags, deleted ones are marked missing and modified ones are hashed again.
+/// Changes are written to `output` as they happen, watching stops after timeout if it's set
+pub fn watch(
+    db: &mut Database,
+    root: &Path,
+    timeout: Option<u64>,
+    mut output: impl Write,
+) -> Result<Option<String>, AppError> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    info!("watching {}", root.display());
+
+    let deadline = timeout.map(|timeout| Instant::now() + Duration::from_secs(timeout));
+    loop {
+        let event = match deadline {
+            Some(deadline) => {
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => event,
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        return Err(AppError::WatchStopped);
+                    }
+                }
+            }
+            None => receiver.recv().map_err(|_| AppError::WatchStopped)?,
+        };
+
+        let event = event?;
+        if event
+            .paths
+            .iter()
+            .any(|path| super::is_anytaggy_file(db, path))
+        {
+            continue;
+        }
+        debug!("event: {event:?}");
+        // Single file failing, e.g. removed right after change, should not stop watching
+        match handle_event(db, root, &event) {
+            Ok(lines) => {
+                for line in lines {
+                    writeln!(output, "{line}")?;
+                }
+                output.flush()?;
+            }
+            Err(err) => warn!("could not handle {event:?}: {err}"),
+        }
+    }
+
+    Ok(None)
+}
+
+fn handle_event(db: &mut Database, root: &Path, event: &Event) -> Result<Vec<String>, AppError> {
+    match (event.kind, event.paths.as_slice()) {
+        (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) => {
+            move_files(db, root, from, to)
+        }
+        // Move source is reported before the move itself, which brings files back
+        (EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_), [path]) => {
+            mark_missing(db, root, path)
+        }
+        // Editors save by renaming new contents over the file
+        (
+            EventKind::Access(AccessKind::Close(AccessMode::Write))
+            | EventKind::Create(CreateKind::File)
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+            [path],
+        ) => rehash_file(db, root, path),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Moves file or every file under moved directory, fingerprints change along with paths
+fn move_files(
+    db: &mut Database,
+    root: &Path,
+    from: &Path,
+    to: &Path,
+) -> Result<Vec<String>, AppError> {
+    let from = stored_path(root, from)?;
+    let to = stored_path(root, to)?;
+
+    let mut lines = Vec::new();
+    for mut file in db.get_files_under(&from)? {
+        let old_path = file.path;
+        file.path = format!("{to}{}", &old_path[from.len()..]);
+        file.name = Path::new(&file.path)
+            .file_name()
+            .unwrap_or_default()
+            .display()
+            .to_string();
+        file.fingerprint_hash = super::get_fingerprint_hash(&file.contents_hash, &file.path)?;
+        db.update_file(&old_path, &file)?;
+        lines.push(format!("Moved: {old_path} -> {}", file.path));
+    }
+
+    Ok(lines)
+}
+
+fn mark_missing(db: &mut Database, root: &Path, path: &Path) -> Result<Vec<String>, AppError> {
+    let mut lines = Vec::new();
+    for file in db.get_files_under(&stored_path(root, path)?)? {
+        db.set_file_missing(&file.path)?;
+        lines.push(format!("Missing: {}", file.path));
+    }
+
+    Ok(lines)
+}
+
+/// Hashes stored file again, files which are not in database are ignored
+fn rehash_file(db: &mut Database, root: &Path, path: &Path) -> Result<Vec<String>, AppError> {
+    let stored_path = stored_path(root, path)?;
+    if !path.is_file()
+        || !db
+            .get_files_under(&stored_path)?
+            .iter()
+            .any(|file| file.path == stored_path)
+    {
+        return Ok(Vec::new());
+    }
+
+    let file = super::prepare_file_arg(root, path)?;
+    db.update_file(&stored_path, &file)?;
+
+    Ok(vec![format!("Updated: {stored_path}")])
+}
+
+fn stored_path(root: &Path, path: &Path) -> Result<String, AppError> {
+    Ok(path
+        .strip_prefix(root)
+        .map_err(|_| AppError::FileOutsideStructure)?
+        .display()
+        .to_string())
+}
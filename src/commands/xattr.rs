@@ -0,0 +1,125 @@
+use crate::{
+    AppError,
+    db::{Database, ImportMode, TaggedFile},
+};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// Extended attribute read by other tools, e.g. Baloo and Dolphin
+pub const XATTR_TAGS: &str = "user.xdg.tags";
+const XATTR_TAG_SEPARATOR: char = ',';
+
+/// Writes tags of every file in database to its extended attribute
+pub fn export(db: &Database, root: &Path) -> Result<Option<String>, AppError> {
+    let mut lines = Vec::new();
+    let mut exported = 0;
+    for tagged_file in db.get_tagged_files()? {
+        let file_path = root.join(&tagged_file.file.path);
+        if !file_path.is_file() {
+            lines.push(format!("Missing: {}", tagged_file.file.path));
+            continue;
+        }
+        write_tags(&file_path, &tagged_file.tags)?;
+        exported += 1;
+    }
+    lines.push(format!("Exported: {exported} files"));
+
+    Ok(Some(lines.join("\n")))
+}
+
+/// Reads tags from extended attribute of files into database.
+/// Directories are searched recursively, symlinks are not followed
+pub fn import(
+    db: &mut Database,
+    root: &Path,
+    paths: &[PathBuf],
+    mode: ImportMode,
+) -> Result<Option<String>, AppError> {
+    let mut file_paths = Vec::new();
+    if paths.is_empty() {
+        super::collect_files(root, &mut file_paths)?;
+    }
+    for path in paths {
+        if path.is_dir() {
+            super::collect_files(path, &mut file_paths)?;
+        } else if path.is_file() {
+            file_paths.push(path.clone());
+        } else {
+            return Err(AppError::FileNotFound);
+        }
+    }
+
+    let mut files = Vec::new();
+    for file_path in file_paths {
+        let tags = read_tags(&file_path)?;
+        if tags.is_empty() {
+            continue;
+        }
+        debug!("{}: {tags:?}", file_path.display());
+        files.push(TaggedFile {
+            file: super::prepare_file_arg(root, &file_path)?,
+            tags,
+        });
+    }
+
+    let tags: Vec<String> = files
+        .iter()
+        .flat_map(|tagged_file| tagged_file.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let report = db.import_files(&tags, &files, mode)?;
+
+    Ok(Some(super::import::format_report(&report, mode)))
+}
+
+/// Keeps extended attribute of the file in sync with its tags in database.
+/// Database is already changed at this point, so failures are only reported as warnings
+pub(super) fn sync_file(db: &Database, file_path: &Path, fingerprint_hash: &str) {
+    let result = db
+        .get_file_tags_by_hash(0, fingerprint_hash)
+        .map_err(AppError::from)
+        .and_then(|tags| write_tags(file_path, &tags));
+    if let Err(err) = result {
+        eprintln!(
+            "WARNING: could not write tags to extended attribute of {}: {err}",
+            file_path.display()
+        );
+    }
+}
+
+/// Tags are sorted, attribute is removed when file has no tags
+fn write_tags(file_path: &Path, tags: &[String]) -> Result<(), AppError> {
+    if tags.is_empty() {
+        if xattr::get(file_path, XATTR_TAGS)?.is_some() {
+            xattr::remove(file_path, XATTR_TAGS)?;
+        }
+        return Ok(());
+    }
+
+    let mut tags = tags.to_vec();
+    tags.sort();
+    xattr::set(
+        file_path,
+        XATTR_TAGS,
+        tags.join(&XATTR_TAG_SEPARATOR.to_string()).as_bytes(),
+    )?;
+
+    Ok(())
+}
+
+fn read_tags(file_path: &Path) -> Result<Vec<String>, AppError> {
+    let Some(value) = xattr::get(file_path, XATTR_TAGS)? else {
+        return Ok(Vec::new());
+    };
+
+    Ok(String::from_utf8_lossy(&value)
+        .split(XATTR_TAG_SEPARATOR)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect())
+}
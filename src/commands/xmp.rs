@@ -0,0 +1,322 @@
+use crate::{
+    AppError,
+    db::{Database, ImportMode, TaggedFile},
+};
+use quick_xml::{
+    NsReader, Writer,
+    escape::{escape, unescape},
+    events::{BytesStart, Event},
+    name::ResolveResult,
+};
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// Sidecars are named after the whole file name, e.g. `photo.jpg.xmp`, like darktable and digiKam do
+const XMP_EXTENSION: &str = "xmp";
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+/// Packet used for files without sidecar, `dc:subject` is added to its description
+const EMPTY_PACKET: &str = "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">
+<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+<rdf:Description rdf:about=\"\"/>
+</rdf:RDF>
+</x:xmpmeta>
+<?xpacket end=\"w\"?>
+";
+
+/// Writes tags of every file in database as `dc:subject` keywords of its XMP sidecar.
+/// Existing keywords are replaced, the rest of the sidecar is kept as is
+pub fn export(db: &Database, root: &Path) -> Result<Option<String>, AppError> {
+    let mut lines = Vec::new();
+    let mut exported = 0;
+    for tagged_file in db.get_tagged_files()? {
+        let file_path = root.join(&tagged_file.file.path);
+        if !file_path.is_file() {
+            lines.push(format!("Missing: {}", tagged_file.file.path));
+            continue;
+        }
+
+        let sidecar_path = sidecar_path(&file_path);
+        let contents = match std::fs::read_to_string(&sidecar_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => EMPTY_PACKET.into(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut tags = tagged_file.tags;
+        tags.sort();
+        let contents = write_keywords(&contents, &tags)
+            .map_err(|err| AppError::InvalidXmp(sidecar_path.clone(), err))?;
+        std::fs::write(&sidecar_path, contents)?;
+        exported += 1;
+    }
+    lines.push(format!("Exported: {exported} files"));
+
+    Ok(Some(lines.join("\n")))
+}
+
+/// Reads `dc:subject` keywords from XMP sidecars into tags of files they describe.
+/// Tags imported before are removed from files once their keywords are gone.
+/// Directories are searched recursively, symlinks are not followed
+pub fn import(
+    db: &mut Database,
+    root: &Path,
+    paths: &[PathBuf],
+    mode: ImportMode,
+) -> Result<Option<String>, AppError> {
+    let mut sidecar_paths = Vec::new();
+    if paths.is_empty() {
+        collect_sidecars(root, &mut sidecar_paths)?;
+    }
+    for path in paths {
+        if path.is_dir() {
+            collect_sidecars(path, &mut sidecar_paths)?;
+        } else if path.is_file() {
+            sidecar_paths.push(path.clone());
+        } else {
+            return Err(AppError::FileNotFound);
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut files = Vec::new();
+    for sidecar_path in sidecar_paths {
+        let file_path = sidecar_path.with_extension("");
+        if !file_path.is_file() {
+            lines.push(format!("Missing: {}", file_path.display()));
+            continue;
+        }
+        // Sidecars without keywords are imported too, to clear keywords imported before
+        let tags = read_keywords(&std::fs::read_to_string(&sidecar_path)?)
+            .map_err(|err| AppError::InvalidXmp(sidecar_path.clone(), err))?;
+        debug!("{}: {tags:?}", sidecar_path.display());
+        files.push(TaggedFile {
+            file: super::prepare_file_arg(root, &file_path)?,
+            tags,
+        });
+    }
+
+    let tags: Vec<String> = files
+        .iter()
+        .flat_map(|tagged_file| tagged_file.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let report = db.import_xmp_keywords(&tags, &files, mode)?;
+    lines.push(super::import::format_report(&report, mode));
+
+    Ok(Some(lines.join("\n")))
+}
+
+fn sidecar_path(file_path: &Path) -> PathBuf {
+    let mut sidecar_path = OsString::from(file_path);
+    sidecar_path.push(".");
+    sidecar_path.push(XMP_EXTENSION);
+    sidecar_path.into()
+}
+
+fn collect_sidecars(directory: &Path, sidecar_paths: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    let mut entries = std::fs::read_dir(directory)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_sidecars(&path, sidecar_paths)?;
+        } else if file_type.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case(XMP_EXTENSION))
+        {
+            sidecar_paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_element(resolved: &ResolveResult, element: &BytesStart, namespace: &str, name: &str) -> bool {
+    matches!(resolved, ResolveResult::Bound(bound) if bound.as_ref() == namespace)
+        && element.local_name().as_ref() == name
+}
+
+/// Prefix of element along with colon, empty for elements in default namespace
+fn prefix(element: &BytesStart) -> String {
+    element
+        .name()
+        .prefix()
+        .map(|prefix| format!("{}:", prefix.as_ref()))
+        .unwrap_or_default()
+}
+
+/// Keywords are `rdf:li` items of `dc:subject`
+fn read_keywords(contents: &str) -> Result<Vec<String>, String> {
+    let mut reader = NsReader::from_str(contents);
+    let mut in_subject = false;
+    let mut keyword: Option<String> = None;
+    let mut keywords = Vec::new();
+    loop {
+        match reader
+            .read_resolved_event()
+            .map_err(|err| err.to_string())?
+        {
+            (resolved, Event::Start(element))
+                if is_element(&resolved, &element, DC_NAMESPACE, "subject") =>
+            {
+                in_subject = true;
+            }
+            (resolved, Event::Start(element))
+                if in_subject && is_element(&resolved, &element, RDF_NAMESPACE, "li") =>
+            {
+                keyword = Some(String::new());
+            }
+            (_, Event::Text(text)) => {
+                if let Some(keyword) = &mut keyword {
+                    keyword.push_str(&text.xml10_content());
+                }
+            }
+            (_, Event::GeneralRef(reference)) => {
+                if let Some(keyword) = &mut keyword {
+                    let escaped = format!("&{};", reference.xml10_content());
+                    keyword.push_str(&unescape(&escaped).map_err(|err| err.to_string())?);
+                }
+            }
+            (resolved, Event::End(element)) => {
+                let name = element.local_name();
+                if name.as_ref() == "subject"
+                    && matches!(&resolved, ResolveResult::Bound(bound) if bound.as_ref() == DC_NAMESPACE)
+                {
+                    in_subject = false;
+                } else if name.as_ref() == "li"
+                    && let Some(keyword) = keyword.take()
+                {
+                    let keyword = keyword.trim();
+                    if !keyword.is_empty() {
+                        keywords.push(keyword.to_string());
+                    }
+                }
+            }
+            (_, Event::Eof) => break,
+            _ => {}
+        }
+    }
+
+    Ok(keywords)
+}
+
+fn has_subject(contents: &str) -> Result<bool, String> {
+    let mut reader = NsReader::from_str(contents);
+    loop {
+        match reader
+            .read_resolved_event()
+            .map_err(|err| err.to_string())?
+        {
+            (resolved, Event::Start(element) | Event::Empty(element))
+                if is_element(&resolved, &element, DC_NAMESPACE, "subject") =>
+            {
+                return Ok(true);
+            }
+            (_, Event::Eof) => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// Replaces `dc:subject` with keywords, adding it to the first `rdf:Description` if it's missing
+fn write_keywords(contents: &str, keywords: &[String]) -> Result<String, String> {
+    let has_subject = has_subject(contents)?;
+    let mut reader = NsReader::from_str(contents);
+    let mut writer = Writer::new(Vec::new());
+    let mut rdf_prefix = String::new();
+    let mut written = false;
+    // Depth of replaced `dc:subject` contents being skipped
+    let mut skip_depth = 0;
+    loop {
+        let (resolved, event) = reader
+            .read_resolved_event()
+            .map_err(|err| err.to_string())?;
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        let (element, is_empty) = match &event {
+            Event::Start(element) => (element, false),
+            Event::Empty(element) => (element, true),
+            Event::Eof => break,
+            event => {
+                writer
+                    .write_event(event.borrow())
+                    .map_err(|err| err.to_string())?;
+                continue;
+            }
+        };
+
+        let is_description = is_element(&resolved, element, RDF_NAMESPACE, "Description");
+        if is_description {
+            rdf_prefix = prefix(element);
+        }
+        if !written && has_subject && is_element(&resolved, element, DC_NAMESPACE, "subject") {
+            let name = element.name().as_ref().to_string();
+            write_subject(&mut writer, &name, "", &rdf_prefix, keywords);
+            written = true;
+            if !is_empty {
+                skip_depth = 1;
+            }
+        } else if !written && !has_subject && is_description {
+            writer
+                .write_event(Event::Start(element.borrow()))
+                .map_err(|err| err.to_string())?;
+            writer.get_mut().push(b'\n');
+            let declaration = format!(" xmlns:dc=\"{}\"", DC_NAMESPACE);
+            write_subject(
+                &mut writer,
+                "dc:subject",
+                &declaration,
+                &rdf_prefix,
+                keywords,
+            );
+            writer.get_mut().push(b'\n');
+            if is_empty {
+                writer
+                    .write_event(Event::End(element.to_end()))
+                    .map_err(|err| err.to_string())?;
+            }
+            written = true;
+        } else {
+            writer
+                .write_event(event.borrow())
+                .map_err(|err| err.to_string())?;
+        }
+    }
+    if !written {
+        return Err("rdf:Description is missing".into());
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(|err| err.to_string())
+}
+
+fn write_subject(
+    writer: &mut Writer<Vec<u8>>,
+    name: &str,
+    declaration: &str,
+    rdf_prefix: &str,
+    keywords: &[String],
+) {
+    let mut subject = format!("<{name}{declaration}>\n<{rdf_prefix}Bag>\n");
+    for keyword in keywords {
+        subject.push_str(&format!(
+            "<{rdf_prefix}li>{}</{rdf_prefix}li>\n",
+            escape(keyword.as_str())
+        ));
+    }
+    subject.push_str(&format!("</{rdf_prefix}Bag>\n</{name}>"));
+    writer.get_mut().extend_from_slice(subject.as_bytes());
+}
@@ -0,0 +1,124 @@
+use crate::AppError;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+pub const CONFIG_ENV: &str = "ANYTAGGY_CONFIG";
+const CONFIG_DIRECTORY: &str = "anytaggy";
+const CONFIG_FILENAME: &str = "config.toml";
+/// Prefix used to reference named databases, e.g. `-d @photos`
+const NAMED_DATABASE_PREFIX: char = '@';
+
+/// User configuration, read from `$XDG_CONFIG_HOME/anytaggy/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Database used when no other database could be found
+    pub default: Option<PathBuf>,
+    /// Databases that could be referenced by name as `@name`
+    #[serde(default)]
+    pub databases: BTreeMap<String, PathBuf>,
+    /// Databases used for specific directories and everything under them
+    #[serde(default)]
+    pub directories: Vec<DirectoryOverride>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DirectoryOverride {
+    pub path: PathBuf,
+    /// Path to database or `@name` of named database
+    pub database: PathBuf,
+}
+
+impl Config {
+    /// Loads config from `ANYTAGGY_CONFIG` or XDG config location.
+    /// Missing config file is the same as an empty one
+    pub fn load() -> Result<Self, AppError> {
+        let Some(config_path) = config_path() else {
+            return Ok(Self::default());
+        };
+        debug!("config_path: {}", config_path.display());
+
+        match std::fs::read_to_string(&config_path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, AppError> {
+        toml::from_str(contents).map_err(|err| AppError::InvalidConfig(err.message().into()))
+    }
+
+    /// Resolves database path or `@name` of named database into path
+    pub fn resolve_database(&self, database: &Path) -> Result<PathBuf, AppError> {
+        match database_name(database) {
+            Some(name) => self
+                .databases
+                .get(name)
+                .map(|path| expand_home(path))
+                .ok_or_else(|| AppError::UnknownNamedDatabase(name.into())),
+            None => Ok(expand_home(database)),
+        }
+    }
+
+    /// Finds database override for the deepest configured directory containing `directory`
+    pub fn directory_database(&self, directory: &Path) -> Result<Option<PathBuf>, AppError> {
+        self.directories
+            .iter()
+            .filter_map(|entry| {
+                let path = expand_home(&entry.path);
+                directory
+                    .starts_with(&path)
+                    .then(|| (path.components().count(), entry))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, entry)| self.resolve_database(&entry.database))
+            .transpose()
+    }
+
+    pub fn default_database(&self) -> Result<Option<PathBuf>, AppError> {
+        self.default
+            .as_deref()
+            .map(|database| self.resolve_database(database))
+            .transpose()
+    }
+}
+
+/// Resolves database path or `@name` of named database into path,
+/// config file is only read for named databases
+pub fn resolve_database_path(database: &Path) -> Result<PathBuf, AppError> {
+    if database_name(database).is_some() {
+        Config::load()?.resolve_database(database)
+    } else {
+        Ok(expand_home(database))
+    }
+}
+
+fn database_name(database: &Path) -> Option<&str> {
+    database
+        .to_str()
+        .and_then(|database| database.strip_prefix(NAMED_DATABASE_PREFIX))
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV) {
+        return Some(path.into());
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join(CONFIG_DIRECTORY).join(CONFIG_FILENAME))
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::db::{
+    Database, DatabaseError, File,
+    tables::{
+        file_tags::{get_file_tag_names_by_id, reference_file_tag, unreference_file_tag},
+        files::{delete_file, get_file_id, get_file_id_by_path},
+        tags::{get_or_create_tag_id, get_tag_id_by_name},
+    },
+};
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use tracing::debug;
+
+/// Namespaces of tags extracted from file metadata, e.g. `exif:camera=...`
+pub const METADATA_NAMESPACES: &[&str] = &["exif:", "audio:"];
+
+/// Metadata tags added and removed by extraction
+#[derive(Debug, Default)]
+pub struct MetadataChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Database {
+    /// Whether metadata tags were extracted from the current contents of the file
+    pub fn is_metadata_current(&self, file: &File) -> Result<bool, DatabaseError> {
+        Ok(get_metadata_hash(&self.connection, &file.path)?.as_deref()
+            == Some(file.contents_hash.as_str()))
+    }
+
+    /// Replaces metadata tags of the file, stored file at the same path is updated to new contents.
+    /// Files not in database are not added, `None` is returned for them.
+    /// File left without any tags is removed from database
+    pub fn set_metadata_tags(
+        &mut self,
+        file: &File,
+        tags: &[String],
+    ) -> Result<Option<MetadataChange>, DatabaseError> {
+        self.write_transaction(|tx| {
+            // Copies of the file at other paths are not matched, they have tags of their own
+            let file_id = if let Some(file_id) = get_file_id(tx, &file.fingerprint_hash)? {
+                file_id
+            } else if let Some(file_id) = get_file_id_by_path(tx, &file.path)? {
+                update_file_contents(tx, file_id, file)?;
+                file_id
+            } else {
+                return Ok(None);
+            };
+
+            let file_tags = get_file_tag_names_by_id(tx, file_id)?;
+            let mut change = MetadataChange::default();
+            for tag in &file_tags {
+                if is_metadata_tag(tag) && !tags.contains(tag) {
+                    if let Some(tag_id) = get_tag_id_by_name(tx, tag)? {
+                        unreference_file_tag(tx, file_id, tag_id)?;
+                    }
+                    change.removed.push(tag.clone());
+                }
+            }
+            for tag in tags {
+                if !file_tags.contains(tag) {
+                    reference_file_tag(tx, file_id, get_or_create_tag_id(tx, tag)?)?;
+                    change.added.push(tag.clone());
+                }
+            }
+
+            if file_tags.len() == change.removed.len() && change.added.is_empty() {
+                delete_file(tx, file_id)?;
+            } else {
+                set_metadata_hash(tx, file_id, &file.contents_hash)?;
+            }
+            debug!("metadata change: {change:?}");
+
+            Ok(Some(change))
+        })
+    }
+}
+
+fn is_metadata_tag(tag: &str) -> bool {
+    METADATA_NAMESPACES
+        .iter()
+        .any(|namespace| tag.starts_with(namespace))
+}
+
+fn get_metadata_hash(conn: &Connection, path: &str) -> Result<Option<String>, rusqlite::Error> {
+    let mut select = conn.prepare(
+        "SELECT metadata_hash
+            FROM files
+            WHERE path = ?1",
+    )?;
+
+    Ok(select
+        .query_one([path], |row| row.get(0))
+        .optional()?
+        .flatten())
+}
+
+fn set_metadata_hash(
+    tx: &Transaction,
+    id: i32,
+    metadata_hash: &str,
+) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "UPDATE files
+            SET metadata_hash = ?2
+            WHERE id = ?1",
+        (id, metadata_hash),
+    )?;
+
+    Ok(())
+}
+
+/// Contents of the file could change since it was stored
+fn update_file_contents(tx: &Transaction, id: i32, file: &File) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "UPDATE files
+            SET contents_hash = ?2, fingerprint_hash = ?3, mime_type = coalesce(?4, mime_type)
+            WHERE id = ?1",
+        (
+            id,
+            &file.contents_hash,
+            &file.fingerprint_hash,
+            &file.mime_type,
+        ),
+    )?;
+
+    Ok(())
+}
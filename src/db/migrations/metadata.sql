@@ -0,0 +1,2 @@
+-- Contents hash of the file when its metadata tags were extracted
+ALTER TABLE files ADD COLUMN metadata_hash TEXT;
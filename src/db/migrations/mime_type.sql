@@ -0,0 +1,3 @@
+-- Sniffed from file contents when file is added, NULL when unknown
+ALTER TABLE files ADD COLUMN mime_type TEXT;
+CREATE INDEX idx_files_mime_type ON files (mime_type);
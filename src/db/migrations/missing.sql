@@ -0,0 +1,2 @@
+-- Set by watch when file disappears from disk, cleared when it's back
+ALTER TABLE files ADD COLUMN missing INTEGER NOT NULL DEFAULT 0;
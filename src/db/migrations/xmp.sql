@@ -0,0 +1,2 @@
+-- Tag was added to the file from keywords of its XMP sidecar
+ALTER TABLE file_tags ADD COLUMN from_xmp INTEGER NOT NULL DEFAULT 0;
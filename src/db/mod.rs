@@ -1,12 +1,39 @@
+mod metadata;
+mod sidecar;
 mod tables;
+mod tmsu;
+mod transfer;
 
-use rusqlite::{Connection, OpenFlags};
+pub use metadata::{METADATA_NAMESPACES, MetadataChange};
+pub use sidecar::{format_tag_line, parse_tag_line, parse_text_sidecar, text_sidecar_path};
+pub use tables::{file_tags::TYPE_FACET_PREFIX, settings::Setting};
+pub use tmsu::{TmsuDatabase, TmsuFile, read_tmsu_database};
+pub use transfer::{FileEdit, ImportMode, ImportReport, MergePreference, MergeReport, TaggedFile};
+
+use rusqlite::{Connection, ErrorCode, OpenFlags, Transaction, TransactionBehavior};
 use rusqlite_migration::{M, Migrations};
-use std::path::Path;
+use std::{path::Path, thread, time::Duration};
 use thiserror::Error;
+use tracing::debug;
 
-const MIGRATIONS_SLICE: &[M] = &[M::up(include_str!("migrations/initial.sql"))];
+const MIGRATIONS_SLICE: &[M] = &[
+    M::up(include_str!("migrations/initial.sql")),
+    M::up(include_str!("migrations/settings.sql")),
+    M::up(include_str!("migrations/xmp.sql")),
+    M::up(include_str!("migrations/mime_type.sql")),
+    M::up(include_str!("migrations/metadata.sql")),
+    M::up(include_str!("migrations/missing.sql")),
+];
 const MIGRATIONS: Migrations = Migrations::from_slice(MIGRATIONS_SLICE);
+pub const SCHEMA_VERSION: usize = MIGRATIONS_SLICE.len();
+
+/// How long to wait for other connections to release database lock
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// How many times write transaction is attempted when database stays busy
+const WRITE_ATTEMPTS: u32 = 3;
+const WRITE_RETRY_DELAY: Duration = Duration::from_millis(50);
+/// `SQLite` limits amount of attached databases to 10 by default
+pub const MAX_ATTACHED_DATABASES: usize = 10;
 
 #[derive(Debug, Clone)]
 pub struct File {
@@ -14,6 +41,16 @@ pub struct File {
     pub name: String,
     pub contents_hash: String,
     pub fingerprint_hash: String,
+    /// Sniffed from file contents, `None` when it's unknown
+    pub mime_type: Option<String>,
+}
+
+/// Path of the file relative to root of the database it's stored in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredPath {
+    /// Index of the database, `0` is the main one, attached databases follow in order of attaching
+    pub database: usize,
+    pub path: String,
 }
 
 #[derive(Debug, Error)]
@@ -24,34 +61,199 @@ pub enum DatabaseError {
     NoSuchTag(String),
     #[error("File did not have such tag: {0}")]
     NoSuchTagOnFile(String),
+    #[error(
+        "Database schema is outdated (version {found}, expected {SCHEMA_VERSION}), run 'anytaggy db upgrade'"
+    )]
+    NeedsUpgrade { found: usize },
+    #[error(
+        "Database schema is newer than this binary supports (version {found}, expected {SCHEMA_VERSION})"
+    )]
+    NewerThanBinary { found: usize },
+    #[error("Invalid line {0} in text sidecar")]
+    InvalidTextSidecar(usize),
+    #[error("Could not write text sidecar: {0}")]
+    TextSidecarWrite(std::io::Error),
+    #[error("Tag names starting with '{TYPE_FACET_PREFIX}' are reserved for file types: {0}")]
+    ReservedTagName(String),
+    #[error("Database is not a TMSU database")]
+    NotTmsu,
+    #[error("Could not resolve TMSU database path: {0}")]
+    TmsuPath(std::io::Error),
+    #[error("Could not attach more than {MAX_ATTACHED_DATABASES} databases")]
+    TooManyAttached,
+    #[error("Could not migrate database: {0}")]
+    Migration(rusqlite_migration::Error),
+    #[error("Database file is corrupted or is not an anytaggy database")]
+    Corrupt,
+    #[error("Database is locked by another process")]
+    Locked,
+    #[error("Database is read-only")]
+    ReadOnly,
     #[error("Unhandled database error: {0}")]
-    DatabaseInternal(#[from] rusqlite::Error),
+    DatabaseInternal(rusqlite::Error),
+}
+
+impl DatabaseError {
+    fn classify(err: &rusqlite::Error) -> Option<Self> {
+        match err.sqlite_error_code()? {
+            ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => Some(Self::Locked),
+            ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => Some(Self::Corrupt),
+            ErrorCode::ReadOnly => Some(Self::ReadOnly),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::classify(&err).unwrap_or(Self::DatabaseInternal(err))
+    }
+}
+
+impl From<rusqlite_migration::Error> for DatabaseError {
+    fn from(err: rusqlite_migration::Error) -> Self {
+        if let rusqlite_migration::Error::RusqliteError { err, .. } = &err
+            && let Some(classified) = Self::classify(err)
+        {
+            return classified;
+        }
+        Self::Migration(err)
+    }
 }
 
 pub enum DatabaseMode {
     ReadWriteCreate,
     ReadWrite,
     Read,
+    /// Opens existing database for writing without checking schema version,
+    /// used to apply pending migrations
+    Upgrade,
 }
 pub struct Database {
     connection: Connection,
+    attached: usize,
 }
 impl Database {
-    fn apply_migrations(&mut self) {
-        MIGRATIONS.to_latest(&mut self.connection).unwrap();
+    fn apply_migrations(&mut self) -> Result<(), DatabaseError> {
+        Ok(MIGRATIONS.to_latest(&mut self.connection)?)
     }
 
-    fn apply_runtime_options(&mut self) {
-        self.connection
-            .execute("PRAGMA foreign_keys = ON", [])
-            .unwrap();
+    fn apply_runtime_options(&mut self) -> Result<(), DatabaseError> {
+        self.connection.execute("PRAGMA foreign_keys = ON", [])?;
+
+        // WAL lets readers work alongside a writer, setting is persisted in the database file
+        let journal_mode: String = self
+            .connection
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            self.connection
+                .query_row("PRAGMA journal_mode = WAL", [], |row| {
+                    row.get::<_, String>(0)
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` inside of a write transaction, retrying it when database is locked by other writers
+    pub(crate) fn write_transaction<T>(
+        &mut self,
+        f: impl Fn(&Transaction) -> Result<T, DatabaseError>,
+    ) -> Result<T, DatabaseError> {
+        self.retried_transaction(true, f)
+    }
+
+    /// Same as [`Database::write_transaction`], but changes are committed only if `commit` is set
+    fn retried_transaction<T>(
+        &mut self,
+        commit: bool,
+        f: impl Fn(&Transaction) -> Result<T, DatabaseError>,
+    ) -> Result<T, DatabaseError> {
+        let mut attempt = 1;
+        loop {
+            let result = (|| {
+                // Immediate transaction takes write lock right away,
+                //  so concurrent writers wait on busy timeout instead of failing on lock upgrade
+                let tx = self
+                    .connection
+                    .transaction_with_behavior(TransactionBehavior::Immediate)?;
+                let out = f(&tx)?;
+                if commit {
+                    tx.commit()?;
+                }
+                Ok(out)
+            })();
+            match result {
+                Ok(out) if commit => {
+                    // Written only once changes are stored, so it never describes a failed write.
+                    // Failure is reported separately, as changes are stored already
+                    if let Err(err) = self.sync_text_sidecar() {
+                        eprintln!("WARNING: {err}");
+                    }
+                    return Ok(out);
+                }
+                Err(DatabaseError::Locked) if attempt < WRITE_ATTEMPTS => {
+                    debug!("database is locked, retrying write (attempt {attempt})");
+                    thread::sleep(WRITE_RETRY_DELAY * attempt);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Rewrites text sidecar while holding write lock, so concurrent writers can't reorder it.
+    /// When lock is still taken by another writer after busy timeout, that writer syncs it
+    /// once it commits
+    fn sync_text_sidecar(&mut self) -> Result<(), DatabaseError> {
+        let tx = match self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(DatabaseError::from)
+        {
+            Ok(tx) => tx,
+            Err(DatabaseError::Locked) => {
+                debug!("database is locked, text sidecar is left to the other writer");
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        sidecar::sync_text_sidecar(&tx)
+    }
+
+    fn schema_version(&self) -> Result<usize, DatabaseError> {
+        Ok(self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    fn is_empty(&self) -> Result<bool, DatabaseError> {
+        let objects: usize =
+            self.connection
+                .query_row("SELECT COUNT(*) FROM sqlite_schema", [], |row| row.get(0))?;
+        Ok(objects == 0)
+    }
+
+    fn check_schema_version(&self) -> Result<(), DatabaseError> {
+        match self.schema_version()? {
+            SCHEMA_VERSION => Ok(()),
+            found if found < SCHEMA_VERSION => Err(DatabaseError::NeedsUpgrade { found }),
+            found => Err(DatabaseError::NewerThanBinary { found }),
+        }
     }
 
-    // todo: the only place where unwrap is used, is it fine?
     pub fn new(database_mode: &DatabaseMode, database_path: &Path) -> Result<Self, DatabaseError> {
+        Self::new_with_busy_timeout(database_mode, database_path, DEFAULT_BUSY_TIMEOUT)
+    }
+
+    pub fn new_with_busy_timeout(
+        database_mode: &DatabaseMode,
+        database_path: &Path,
+        busy_timeout: Duration,
+    ) -> Result<Self, DatabaseError> {
         let connection = match database_mode {
             DatabaseMode::ReadWriteCreate => Connection::open(database_path)?,
-            DatabaseMode::ReadWrite => Connection::open_with_flags(
+            DatabaseMode::ReadWrite | DatabaseMode::Upgrade => Connection::open_with_flags(
                 database_path,
                 OpenFlags::SQLITE_OPEN_READ_WRITE
                     | OpenFlags::SQLITE_OPEN_NO_MUTEX
@@ -64,15 +266,86 @@ impl Database {
                     | OpenFlags::SQLITE_OPEN_URI,
             )?,
         };
+        connection.busy_timeout(busy_timeout)?;
+        let mut db = Self {
+            connection,
+            attached: 0,
+        };
         match database_mode {
-            DatabaseMode::ReadWrite | DatabaseMode::ReadWriteCreate => {
-                // todo: is it good idea to use migrations here?
-                let mut db = Self { connection };
-                db.apply_runtime_options();
-                db.apply_migrations();
-                Ok(db)
+            DatabaseMode::ReadWriteCreate => {
+                db.apply_runtime_options()?;
+                // Only freshly created database gets migrated implicitly,
+                // existing ones have to be upgraded explicitly
+                if db.is_empty()?
+                    && let Err(err) = db.apply_migrations()
+                    // Another process could've created the same database concurrently
+                    && db.schema_version()? != SCHEMA_VERSION
+                {
+                    return Err(err);
+                }
+                db.check_schema_version()?;
             }
-            DatabaseMode::Read => Ok(Self { connection }),
+            DatabaseMode::ReadWrite => {
+                db.apply_runtime_options()?;
+                db.check_schema_version()?;
+            }
+            DatabaseMode::Read => db.check_schema_version()?,
+            DatabaseMode::Upgrade => {
+                db.apply_runtime_options()?;
+                let found = db.schema_version()?;
+                if found > SCHEMA_VERSION {
+                    return Err(DatabaseError::NewerThanBinary { found });
+                }
+            }
+        }
+        Ok(db)
+    }
+
+    /// Path of the main database file, `None` for in-memory databases
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.connection
+            .path()
+            .filter(|path| !path.is_empty())
+            .map(Path::new)
+    }
+
+    /// Applies all pending migrations, returns resulting schema version
+    pub fn upgrade(&mut self) -> Result<usize, DatabaseError> {
+        self.apply_migrations()?;
+        self.schema_version()
+    }
+
+    /// Attaches another database to be queried along with this one, returns its index.
+    /// Attached database is opened with the same access mode
+    pub fn attach(&mut self, database_path: &Path) -> Result<usize, DatabaseError> {
+        if self.attached == MAX_ATTACHED_DATABASES {
+            return Err(DatabaseError::TooManyAttached);
         }
+        // Makes sure attached database is valid and has the same schema
+        Self::new(&DatabaseMode::Read, database_path)?;
+
+        let index = self.attached + 1;
+        self.connection.execute(
+            "ATTACH DATABASE ?1 AS ?2",
+            (database_path.display().to_string(), schema_name(index)),
+        )?;
+        self.attached = index;
+        debug!("attached {} as {index}", database_path.display());
+
+        Ok(index)
+    }
+
+    /// Indexes and schema names of main and all attached databases
+    fn schemas(&self) -> impl Iterator<Item = (usize, String)> {
+        (0..=self.attached).map(|index| (index, schema_name(index)))
+    }
+}
+
+fn schema_name(index: usize) -> String {
+    if index == 0 {
+        "main".into()
+    } else {
+        format!("attached_{index}")
     }
 }
@@ -0,0 +1,147 @@
+use crate::db::{
+    DatabaseError, Setting,
+    tables::settings::is_setting_enabled,
+    transfer::{TaggedFile, get_tagged_files},
+};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+const TEXT_SIDECAR_EXTENSION: &str = "txt";
+const PATH_SEPARATOR: char = '\t';
+const TAG_SEPARATOR: char = ',';
+
+/// Text sidecar is stored next to the database, e.g. `.anytaggy.db` is mirrored to `.anytaggy.txt`
+#[must_use]
+pub fn text_sidecar_path(database_path: &Path) -> PathBuf {
+    database_path.with_extension(TEXT_SIDECAR_EXTENSION)
+}
+
+/// Rewrites text sidecar if it's enabled for the database
+pub(super) fn sync_text_sidecar(conn: &Connection) -> Result<(), DatabaseError> {
+    if !is_setting_enabled(conn, Setting::TextSidecar)? {
+        return Ok(());
+    }
+    let Some(database_path) = conn.path().filter(|path| !path.is_empty()) else {
+        return Ok(());
+    };
+    let sidecar_path = text_sidecar_path(Path::new(database_path));
+    debug!("syncing text sidecar: {}", sidecar_path.display());
+
+    let contents = format_text_sidecar(get_tagged_files(conn)?);
+    // Replaced atomically, so readers never see partially written file
+    let temporary_path = sidecar_path.with_extension("txt.tmp");
+    std::fs::write(&temporary_path, contents).map_err(DatabaseError::TextSidecarWrite)?;
+    std::fs::rename(&temporary_path, &sidecar_path).map_err(DatabaseError::TextSidecarWrite)?;
+
+    Ok(())
+}
+
+/// One `path<TAB>tag1,tag2` line per file, both files and tags are sorted to keep diffs small
+fn format_text_sidecar(mut files: Vec<TaggedFile>) -> String {
+    files.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+    files
+        .into_iter()
+        .map(|mut tagged_file| {
+            tagged_file.tags.sort();
+            format!(
+                "{}\n",
+                format_tag_line(&tagged_file.file.path, &tagged_file.tags)
+            )
+        })
+        .collect()
+}
+
+/// Single `path<TAB>tag1,tag2` line, separators inside of path and tags are escaped
+#[must_use]
+pub fn format_tag_line(path: &str, tags: &[String]) -> String {
+    let tags: Vec<String> = tags.iter().map(|tag| escape(tag)).collect();
+    format!(
+        "{}{PATH_SEPARATOR}{}",
+        escape(path),
+        tags.join(&TAG_SEPARATOR.to_string())
+    )
+}
+
+/// Parses line made by [`format_tag_line`], tags could be empty
+#[must_use]
+pub fn parse_tag_line(line: &str) -> Option<(String, Vec<String>)> {
+    let mut fields = split_unescaped(line, PATH_SEPARATOR).into_iter();
+    let (Some(path), Some(tags), None) = (fields.next(), fields.next(), fields.next()) else {
+        return None;
+    };
+    let path = unescape(&path)?;
+    let tags = split_unescaped(&tags, TAG_SEPARATOR)
+        .iter()
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| unescape(tag))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((path, tags))
+}
+
+/// Parses text sidecar into paths with their tags
+pub fn parse_text_sidecar(contents: &str) -> Result<Vec<(String, Vec<String>)>, DatabaseError> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| match parse_tag_line(line) {
+            Some((path, tags)) if !path.is_empty() && !tags.is_empty() => Ok((path, tags)),
+            _ => Err(DatabaseError::InvalidTextSidecar(index + 1)),
+        })
+        .collect()
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            TAG_SEPARATOR => escaped.push_str("\\,"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                '\\' => unescaped.push('\\'),
+                't' => unescaped.push('\t'),
+                'n' => unescaped.push('\n'),
+                TAG_SEPARATOR => unescaped.push(TAG_SEPARATOR),
+                _ => return None,
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Some(unescaped)
+}
+
+/// Splits on separator, skipping escaped ones
+fn split_unescaped(value: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let current = parts.last_mut().expect("parts are never empty");
+        if c == '\\' {
+            current.push(c);
+            if let Some(escaped) = chars.next() {
+                current.push(escaped);
+            }
+        } else if c == separator {
+            parts.push(String::new());
+        } else {
+            current.push(c);
+        }
+    }
+    parts
+}
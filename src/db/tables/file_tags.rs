@@ -1,17 +1,33 @@
-use crate::db::{Database, DatabaseError};
+use crate::db::{Database, DatabaseError, StoredPath, schema_name};
 use rusqlite::{Connection, Transaction};
 use tracing::debug;
 
+/// Tags starting with it select files by their type instead, e.g. `type:image` or `type:image/jpeg`
+pub const TYPE_FACET_PREFIX: &str = "type:";
+
 impl Database {
+    /// Tags of the file from specified database, `0` is the main one
     pub fn get_file_tags_by_hash(
         &self,
+        database: usize,
         fingerprint_hash: &str,
     ) -> Result<Vec<String>, DatabaseError> {
-        get_file_tags_by_hash(&self.connection, fingerprint_hash)
+        get_file_tags_by_hash(&self.connection, &schema_name(database), fingerprint_hash)
+    }
+
+    /// Files having all of the tags, from main and all attached databases
+    pub fn get_files_by_tags(&self, tag_names: &[&str]) -> Result<Vec<StoredPath>, DatabaseError> {
+        let schemas: Vec<_> = self.schemas().collect();
+        get_file_paths_by_tags(&self.connection, &schemas, tag_names)
     }
 
-    pub fn get_files_by_tags(&self, tag_names: &[&str]) -> Result<Vec<String>, DatabaseError> {
-        get_file_paths_by_tags(&self.connection, tag_names)
+    /// Tags of files having all of the tags, from main and all attached databases
+    pub fn get_tags_of_files_by_tags(
+        &self,
+        tag_names: &[&str],
+    ) -> Result<Vec<String>, DatabaseError> {
+        let schemas: Vec<_> = self.schemas().collect();
+        get_tags_of_files_by_tags(&self.connection, &schemas, tag_names)
     }
 }
 
@@ -45,20 +61,70 @@ pub fn reference_file_tag(
     Ok(())
 }
 
+/// Marks tag of the file as added from keywords of its XMP sidecar
+pub fn set_file_tag_from_xmp(
+    tx: &Transaction,
+    file_id: i32,
+    tag_id: i32,
+) -> Result<(), DatabaseError> {
+    tx.execute(
+        "UPDATE file_tags
+             SET from_xmp = 1
+             WHERE file_id = ?1 AND tag_id = ?2",
+        (file_id, tag_id),
+    )?;
+
+    Ok(())
+}
+
+/// Tags of the file added from keywords of its XMP sidecar
+pub fn get_file_xmp_tags_by_id(
+    conn: &Connection,
+    file_id: i32,
+) -> Result<Vec<(i32, String)>, DatabaseError> {
+    let mut statement = conn.prepare(
+        "SELECT t.id, t.name
+        FROM tags t
+            INNER JOIN file_tags ON file_tags.tag_id = t.id
+        WHERE file_tags.file_id = ?1 AND file_tags.from_xmp = 1",
+    )?;
+    Ok(statement
+        .query_map([&file_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect())
+}
+
 fn get_file_tags_by_hash(
     conn: &Connection,
+    schema: &str,
     fingerprint_hash: &str,
+) -> Result<Vec<String>, DatabaseError> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT t.name 
+        FROM {schema}.tags t 
+            INNER JOIN {schema}.file_tags ON file_tags.tag_id = t.id 
+            INNER JOIN {schema}.files ON file_tags.file_id = files.id
+        WHERE files.fingerprint_hash = ?1"
+    ))?;
+
+    Ok(statement
+        .query_map([&fingerprint_hash], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect())
+}
+
+pub fn get_file_tag_names_by_id(
+    conn: &Connection,
+    file_id: i32,
 ) -> Result<Vec<String>, DatabaseError> {
     let mut statement = conn.prepare(
         "SELECT t.name 
         FROM tags t 
             INNER JOIN file_tags ON file_tags.tag_id = t.id 
-            INNER JOIN files ON file_tags.file_id = files.id
-        WHERE files.fingerprint_hash = ?1",
+        WHERE file_tags.file_id = ?1",
     )?;
-
     Ok(statement
-        .query_map([&fingerprint_hash], |row| row.get(0))?
+        .query_map([&file_id], |row| row.get(0))?
         .filter_map(Result::ok)
         .collect())
 }
@@ -79,31 +145,108 @@ pub fn get_file_tag_ids_by_id(conn: &Connection, file_id: i32) -> Result<Vec<i32
 
 fn get_file_paths_by_tags(
     conn: &Connection,
+    schemas: &[(usize, String)],
+    tag_names: &[&str],
+) -> Result<Vec<StoredPath>, DatabaseError> {
+    let (tag_names, types) = split_types(tag_names);
+    let query = schemas
+        .iter()
+        .map(|(index, schema)| {
+            format!(
+                "
+        SELECT {index}, f.path
+        FROM {schema}.files f
+        WHERE {}",
+                files_by_tags_conditions(schema, tag_names.len(), types.len())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+    let mut statement = conn.prepare(&query)?;
+    Ok(statement
+        .query_map(
+            rusqlite::params_from_iter(tag_names.iter().chain(&types)),
+            |row| {
+                Ok(StoredPath {
+                    database: row.get(0)?,
+                    path: row.get(1)?,
+                })
+            },
+        )?
+        .filter_map(Result::ok)
+        .collect())
+}
+
+fn get_tags_of_files_by_tags(
+    conn: &Connection,
+    schemas: &[(usize, String)],
     tag_names: &[&str],
 ) -> Result<Vec<String>, DatabaseError> {
-    let tag_names: Vec<String> = tag_names
+    let (tag_names, types) = split_types(tag_names);
+    let query = schemas
         .iter()
-        .map(|tag_name| format!("'{tag_name}'"))
+        .map(|(_, schema)| {
+            format!(
+                "
+        SELECT t.name
+        FROM {schema}.files f
+            INNER JOIN {schema}.file_tags ft ON ft.file_id = f.id
+            INNER JOIN {schema}.tags t ON ft.tag_id = t.id
+        WHERE {}",
+                files_by_tags_conditions(schema, tag_names.len(), types.len())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ");
+    let mut statement = conn.prepare(&query)?;
+    Ok(statement
+        .query_map(
+            rusqlite::params_from_iter(tag_names.iter().chain(&types)),
+            |row| row.get(0),
+        )?
+        .filter_map(Result::ok)
+        .collect())
+}
+
+/// Splits `type:` facets from tag names, facets are returned without the prefix
+fn split_types<'a>(tag_names: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    let (types, tag_names): (Vec<&str>, Vec<&str>) = tag_names
+        .iter()
+        .partition(|name| name.starts_with(TYPE_FACET_PREFIX));
+    let types = types
+        .iter()
+        .map(|name| &name[TYPE_FACET_PREFIX.len()..])
         .collect();
-    // adapted from: https://dba.stackexchange.com/questions/267559/how-to-filter-multiple-many-to-many-relationship-based-on-multiple-tags#
-    let query = format!(
-        "
-        SELECT f.path
-        FROM files f
-        WHERE f.id IN (
+
+    (tag_names, types)
+}
+
+/// Conditions on `f` file of the schema, tag names are bound first, then types.
+/// Files marked missing never match
+fn files_by_tags_conditions(schema: &str, tags_count: usize, types_count: usize) -> String {
+    let mut conditions = vec!["f.missing = 0".to_string()];
+    if tags_count > 0 {
+        let placeholders: Vec<String> = (1..=tags_count).map(|index| format!("?{index}")).collect();
+        // adapted from: https://dba.stackexchange.com/questions/267559/how-to-filter-multiple-many-to-many-relationship-based-on-multiple-tags#
+        conditions.push(format!(
+            "f.id IN (
             SELECT ft.file_id
-            FROM file_tags ft
-                INNER JOIN tags t on ft.tag_id = t.id
+            FROM {schema}.file_tags ft
+                INNER JOIN {schema}.tags t on ft.tag_id = t.id
             WHERE t.name IN ({})
             GROUP BY ft.file_id
-            HAVING COUNT(*) = {}
+            HAVING COUNT(*) = {tags_count}
             )",
-        tag_names.join(","),
-        tag_names.len()
-    );
-    let mut statement = conn.prepare(&query)?;
-    Ok(statement
-        .query_map([], |row| row.get(0))?
-        .filter_map(Result::ok)
-        .collect())
+            placeholders.join(",")
+        ));
+    }
+    // `type:image` matches all images, `type:image/jpeg` only the exact type
+    conditions.extend((1..=types_count).map(|type_index| {
+        let param = tags_count + type_index;
+        format!(
+            "(f.mime_type = ?{param} OR substr(f.mime_type, 1, length(?{param}) + 1) = ?{param} || '/')"
+        )
+    }));
+
+    conditions.join(" AND ")
 }
@@ -1,5 +1,5 @@
 use crate::db::{
-    Database, DatabaseError, File,
+    Database, DatabaseError, File, StoredPath,
     tables::{
         file_tags::{get_file_tag_ids_by_id, unreference_file_tag},
         tags::get_tag_by_name,
@@ -16,46 +16,183 @@ pub(in crate::db) struct DbFile {
     pub name: String,
     pub contents_hash: String,
     pub fingerprint_hash: String,
+    pub mime_type: Option<String>,
 }
 
 impl Database {
-    pub fn get_files(&self) -> Result<Vec<String>, DatabaseError> {
-        get_all_files_path(&self.connection).map_err(DatabaseError::DatabaseInternal)
+    /// Files from main and all attached databases
+    pub fn get_files(&self) -> Result<Vec<StoredPath>, DatabaseError> {
+        let schemas: Vec<_> = self.schemas().collect();
+        get_all_files_path(&self.connection, &schemas).map_err(DatabaseError::from)
     }
+    /// Files of the main database stored at the path or under it, when path is a directory
+    pub fn get_files_under(&self, path: &str) -> Result<Vec<File>, DatabaseError> {
+        let mut query = self.connection.prepare(
+            "SELECT path, name, contents_hash, fingerprint_hash, mime_type
+                FROM files
+                WHERE path = ?1 OR substr(path, 1, length(?1) + 1) = ?1 || '/'
+                ORDER BY path",
+        )?;
+
+        Ok(query
+            .query_map([path], |row| {
+                Ok(File {
+                    path: row.get(0)?,
+                    name: row.get(1)?,
+                    contents_hash: row.get(2)?,
+                    fingerprint_hash: row.get(3)?,
+                    mime_type: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?)
+    }
+    /// All stored files, with paths as they were stored
+    pub fn get_stored_files(&self) -> Result<Vec<File>, DatabaseError> {
+        let mut query = self.connection.prepare(
+            "SELECT path, name, contents_hash, fingerprint_hash, mime_type
+                FROM files",
+        )?;
+
+        Ok(query
+            .query_map([], |row| {
+                Ok(File {
+                    path: row.get(0)?,
+                    name: row.get(1)?,
+                    contents_hash: row.get(2)?,
+                    fingerprint_hash: row.get(3)?,
+                    mime_type: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?)
+    }
+    /// Sets new path, fingerprint and type of files, matched by their contents
+    pub fn update_stored_files(&mut self, files: &[File]) -> Result<(), DatabaseError> {
+        self.write_transaction(|tx| {
+            for file in files {
+                tx.execute(
+                    "UPDATE files
+                        SET path = ?1, fingerprint_hash = ?2, mime_type = ?3
+                        WHERE contents_hash = ?4",
+                    (
+                        &file.path,
+                        &file.fingerprint_hash,
+                        &file.mime_type,
+                        &file.contents_hash,
+                    ),
+                )?;
+                debug!("updated {file:?}");
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Replaces path and hashes of the file stored at `path`, keeping its tags.
+    /// File previously stored at the new path is deleted, as it was overwritten,
+    /// its tags are kept even when no other file has them
+    pub fn update_file(&mut self, path: &str, file: &File) -> Result<(), DatabaseError> {
+        self.write_transaction(|tx| {
+            if file.path != path
+                && let Some(overwritten) = get_file_id_by_path(tx, &file.path)?
+            {
+                delete_file(tx, overwritten)?;
+                debug!("deleted file overwritten at {}", file.path);
+            }
+            let updated = tx.execute(
+                "UPDATE files
+                    SET path = ?2, name = ?3, contents_hash = ?4, fingerprint_hash = ?5,
+                        mime_type = coalesce(?6, mime_type), missing = 0
+                    WHERE path = ?1",
+                (
+                    path,
+                    &file.path,
+                    &file.name,
+                    &file.contents_hash,
+                    &file.fingerprint_hash,
+                    &file.mime_type,
+                ),
+            )?;
+            if updated == 0 {
+                return Err(DatabaseError::NoSuchFile);
+            }
+            debug!("updated file {path} to {file:?}");
+
+            Ok(())
+        })
+    }
+
+    /// Marks file as gone from disk, it keeps its tags in case it's back.
+    /// Missing files are left out of listings until then
+    pub fn set_file_missing(&mut self, path: &str) -> Result<(), DatabaseError> {
+        self.write_transaction(|tx| {
+            tx.execute(
+                "UPDATE files
+                    SET missing = 1
+                    WHERE path = ?1",
+                [path],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    /// Paths of files marked missing in the main database
+    pub fn get_missing_files(&self) -> Result<Vec<String>, DatabaseError> {
+        Ok(get_missing_files(&self.connection)?
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect())
+    }
+
+    /// Deletes files marked missing, returns their paths.
+    /// Tags are kept even when no other file has them
+    pub fn delete_missing_files(&mut self) -> Result<Vec<String>, DatabaseError> {
+        self.write_transaction(|tx| {
+            let mut paths = Vec::new();
+            for (id, path) in get_missing_files(tx)? {
+                delete_file(tx, id)?;
+                paths.push(path);
+            }
+
+            Ok(paths)
+        })
+    }
+
     pub fn untag_file(&mut self, file: &File, tag_names: &[&str]) -> Result<(), DatabaseError> {
-        let tx = self.connection.transaction()?;
+        self.write_transaction(|tx| untag_file(tx, file, tag_names))
+    }
+}
 
-        let Some(file_id) = get_file_id(&tx, &file.fingerprint_hash)? else {
-            return Err(DatabaseError::NoSuchFile);
+/// Removes tags from file matched by fingerprint, file without tags left is deleted
+pub fn untag_file(tx: &Transaction, file: &File, tag_names: &[&str]) -> Result<(), DatabaseError> {
+    let Some(file_id) = get_file_id(tx, &file.fingerprint_hash)? else {
+        return Err(DatabaseError::NoSuchFile);
+    };
+    debug!("found file_id {file_id}");
+
+    let mut unreferenced_tags_count = 0;
+    let file_tag_ids = get_file_tag_ids_by_id(tx, file_id)?;
+    for tag_name in tag_names {
+        let Some(tag) = get_tag_by_name(tx, tag_name)? else {
+            return Err(DatabaseError::NoSuchTag((*tag_name).into()));
         };
-        debug!("found file_id {file_id}");
-
-        let mut unreferenced_tags_count = 0;
-        let file_tag_ids = get_file_tag_ids_by_id(&tx, file_id)?;
-        for tag_name in tag_names {
-            let Some(tag) = get_tag_by_name(&tx, tag_name)? else {
-                return Err(DatabaseError::NoSuchTag((*tag_name).into()));
-            };
-            debug!("found tag_id {}", tag.id);
-
-            if file_tag_ids.contains(&tag.id) {
-                unreference_file_tag(&tx, file_id, tag.id)?;
-                unreferenced_tags_count += 1;
-            } else {
-                return Err(DatabaseError::NoSuchTagOnFile(tag.name));
-            }
-        }
+        debug!("found tag_id {}", tag.id);
 
-        // if we deleted all tags from file
-        if file_tag_ids.len() == unreferenced_tags_count {
-            // delete the file from database as unnecessary
-            delete_file(&tx, file_id)?;
+        if file_tag_ids.contains(&tag.id) {
+            unreference_file_tag(tx, file_id, tag.id)?;
+            unreferenced_tags_count += 1;
+        } else {
+            return Err(DatabaseError::NoSuchTagOnFile(tag.name));
         }
+    }
 
-        tx.commit()?;
-
-        Ok(())
+    // if we deleted all tags from file
+    if file_tag_ids.len() == unreferenced_tags_count {
+        // delete the file from database as unnecessary
+        delete_file(tx, file_id)?;
     }
+
+    Ok(())
 }
 
 pub fn delete_file(tx: &Transaction, id: i32) -> Result<(), rusqlite::Error> {
@@ -71,9 +208,9 @@ pub fn delete_file(tx: &Transaction, id: i32) -> Result<(), rusqlite::Error> {
 
 pub fn create_file(tx: &Transaction, file: &File) -> Result<DbFile, rusqlite::Error> {
     let mut insert = tx.prepare(
-        "INSERT INTO files (path, name, contents_hash, fingerprint_hash) 
-             VALUES (?1, ?2, ?3, ?4) 
-             RETURNING id, path, name, contents_hash, fingerprint_hash",
+        "INSERT INTO files (path, name, contents_hash, fingerprint_hash, mime_type) 
+             VALUES (?1, ?2, ?3, ?4, ?5) 
+             RETURNING id, path, name, contents_hash, fingerprint_hash, mime_type",
     )?;
 
     let db_file = insert.query_one(
@@ -82,6 +219,7 @@ pub fn create_file(tx: &Transaction, file: &File) -> Result<DbFile, rusqlite::Er
             &file.name,
             &file.contents_hash,
             &file.fingerprint_hash,
+            &file.mime_type,
         ),
         |row| {
             Ok(DbFile {
@@ -90,6 +228,7 @@ pub fn create_file(tx: &Transaction, file: &File) -> Result<DbFile, rusqlite::Er
                 name: row.get(2)?,
                 contents_hash: row.get(3)?,
                 fingerprint_hash: row.get(4)?,
+                mime_type: row.get(5)?,
             })
         },
     )?;
@@ -98,6 +237,24 @@ pub fn create_file(tx: &Transaction, file: &File) -> Result<DbFile, rusqlite::Er
     Ok(db_file)
 }
 
+/// Files stored before types were detected have no type, it's filled when they are tagged again
+pub fn fill_mime_type(
+    tx: &Transaction,
+    id: i32,
+    mime_type: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    if mime_type.is_some() {
+        tx.execute(
+            "UPDATE files
+                SET mime_type = ?2
+                WHERE id = ?1 AND mime_type IS NULL",
+            (id, mime_type),
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn get_file_id(
     conn: &Connection,
     fingerprint_hash: &str,
@@ -113,14 +270,103 @@ pub fn get_file_id(
         .optional()
 }
 
-fn get_all_files_path(conn: &Connection) -> Result<Vec<String>, rusqlite::Error> {
+pub fn get_file_id_by_path(conn: &Connection, path: &str) -> Result<Option<i32>, rusqlite::Error> {
+    let mut select = conn.prepare(
+        "SELECT id
+            FROM files
+            WHERE path = ?1",
+    )?;
+
+    select.query_one([path], |row| row.get(0)).optional()
+}
+
+/// Looks for a file by its path, falling back to contents hash for moved files.
+/// Returns id and path of the file stored in database
+pub fn find_file(
+    conn: &Connection,
+    path: &str,
+    contents_hash: &str,
+) -> Result<Option<(i32, String)>, rusqlite::Error> {
+    let mut select = conn.prepare(
+        "SELECT id, path 
+            FROM files 
+            WHERE path = ?1 OR contents_hash = ?2
+            ORDER BY path = ?1 DESC
+            LIMIT 1",
+    )?;
+
+    select
+        .query_one((path, contents_hash), |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+}
+
+pub fn get_all_files(conn: &Connection) -> Result<Vec<DbFile>, rusqlite::Error> {
     let mut query = conn.prepare(
-        "SELECT path 
-            FROM files",
+        "SELECT id, path, name, contents_hash, fingerprint_hash, mime_type 
+            FROM files
+            WHERE missing = 0
+            ORDER BY path",
     )?;
 
     Ok(query
-        .query_map([], |row| row.get(0))?
+        .query_map([], |row| {
+            Ok(DbFile {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                name: row.get(2)?,
+                contents_hash: row.get(3)?,
+                fingerprint_hash: row.get(4)?,
+                mime_type: row.get(5)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect())
+}
+
+pub fn delete_all_files(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute("DELETE FROM files", [])?;
+    debug!("deleted all files");
+
+    Ok(())
+}
+
+fn get_missing_files(conn: &Connection) -> Result<Vec<(i32, String)>, rusqlite::Error> {
+    let mut query = conn.prepare(
+        "SELECT id, path
+            FROM files
+            WHERE missing = 1
+            ORDER BY path",
+    )?;
+
+    query
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect()
+}
+
+fn get_all_files_path(
+    conn: &Connection,
+    schemas: &[(usize, String)],
+) -> Result<Vec<StoredPath>, rusqlite::Error> {
+    let query = schemas
+        .iter()
+        .map(|(index, schema)| {
+            format!(
+                "SELECT {index}, path 
+            FROM {schema}.files
+            WHERE missing = 0"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+    let mut query = conn.prepare(&query)?;
+
+    Ok(query
+        .query_map([], |row| {
+            Ok(StoredPath {
+                database: row.get(0)?,
+                path: row.get(1)?,
+            })
+        })?
         .filter_map(Result::ok)
         .collect())
 }
@@ -1,3 +1,4 @@
 pub mod file_tags;
 pub mod files;
+pub mod settings;
 pub mod tags;
@@ -0,0 +1,64 @@
+use crate::db::{Database, DatabaseError};
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use tracing::debug;
+
+/// Per-database options, disabled unless set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Setting {
+    /// Include databases nested under database root into queries,
+    /// and tag files in the innermost database containing them
+    IncludeNested,
+    /// Mirror database into sorted text file next to it on every change,
+    /// so it could be reviewed and merged with version control
+    TextSidecar,
+    /// Keep 'user.xdg.tags' extended attribute of files in sync on 'tag' and 'untag'
+    XattrSync,
+    /// Apply auto-tagging rules from database root on every 'tag'
+    Autotag,
+}
+
+impl Setting {
+    fn name(self) -> &'static str {
+        match self {
+            Setting::IncludeNested => "include_nested",
+            Setting::TextSidecar => "text_sidecar",
+            Setting::XattrSync => "xattr_sync",
+            Setting::Autotag => "autotag",
+        }
+    }
+}
+
+impl Database {
+    pub fn get_setting(&self, setting: Setting) -> Result<bool, DatabaseError> {
+        is_setting_enabled(&self.connection, setting)
+    }
+
+    pub fn set_setting(&mut self, setting: Setting, enabled: bool) -> Result<(), DatabaseError> {
+        self.write_transaction(|tx| set_setting_value(tx, setting.name(), &enabled.to_string()))
+    }
+}
+
+pub fn is_setting_enabled(conn: &Connection, setting: Setting) -> Result<bool, DatabaseError> {
+    Ok(get_setting_value(conn, setting.name())?.as_deref() == Some("true"))
+}
+
+fn get_setting_value(conn: &Connection, name: &str) -> Result<Option<String>, DatabaseError> {
+    let mut query = conn.prepare(
+        "SELECT value FROM settings 
+             WHERE name = ?1",
+    )?;
+
+    Ok(query.query_one([name], |row| row.get(0)).optional()?)
+}
+
+fn set_setting_value(tx: &Transaction, name: &str, value: &str) -> Result<(), DatabaseError> {
+    tx.execute(
+        "INSERT INTO settings (name, value) 
+             VALUES (?1, ?2)
+             ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+        (name, value),
+    )?;
+    debug!("set setting {name} to {value}");
+
+    Ok(())
+}
@@ -1,8 +1,8 @@
 use crate::db::{
     Database, DatabaseError, File,
     tables::{
-        file_tags::{get_file_tag_ids_by_id, reference_file_tag},
-        files::{create_file, get_file_id},
+        file_tags::{TYPE_FACET_PREFIX, get_file_tag_ids_by_id, reference_file_tag},
+        files::{create_file, fill_mime_type, get_file_id},
     },
 };
 use rusqlite::{Connection, OptionalExtension, Transaction};
@@ -17,51 +17,95 @@ pub(in crate::db) struct DbTag {
 
 impl Database {
     pub fn tag_file(&mut self, file: &File, tag_names: &[&str]) -> Result<(), DatabaseError> {
-        let tx = self.connection.transaction()?;
-
-        let file_id = get_file_id(&tx, &file.fingerprint_hash)?
-            .map_or_else(|| create_file(&tx, file).map(|f| f.id), Ok)?;
-        debug!("file_id: {file_id}");
-
-        let file_tag_ids = get_file_tag_ids_by_id(&tx, file_id)?;
-        for tag_name in tag_names {
-            let tag_name = tag_name.trim();
-            let tag_id = get_tag_id_by_name(&tx, tag_name)?.map_or_else(
-                || {
-                    let tag_id = create_tag(&tx, tag_name).map(|tag| tag.id);
-                    info!("created tag: {tag_name}");
-                    tag_id
-                },
-                Ok,
-            )?;
-            debug!("tag_id: {tag_id}");
-
-            if !file_tag_ids.contains(&tag_id) {
-                reference_file_tag(&tx, file_id, tag_id)?;
-            }
-        }
-
-        tx.commit()?;
+        self.write_transaction(|tx| tag_file(tx, file, tag_names))
+    }
 
-        Ok(())
+    /// Creates tag without any files, existing tag is left as is
+    pub fn create_tag(&mut self, name: &str) -> Result<(), DatabaseError> {
+        self.write_transaction(|tx| get_or_create_tag_id(tx, name).map(|_| ()))
     }
 
+    /// Tags from main and all attached databases, without duplicates
     pub fn get_all_tags(&self) -> Result<Vec<String>, DatabaseError> {
-        get_tag_names(&self.connection)
+        let schemas: Vec<_> = self.schemas().map(|(_, schema)| schema).collect();
+        get_tag_names(&self.connection, &schemas)
+    }
+
+    /// Tags from main and all attached databases with count of their files which are not missing,
+    /// sorted by name
+    pub fn get_tag_counts(&self) -> Result<Vec<(String, usize)>, DatabaseError> {
+        let counts = self
+            .schemas()
+            .map(|(_, schema)| {
+                format!(
+                    "SELECT t.name, count(f.id) AS files
+                        FROM {schema}.tags t
+                        LEFT JOIN {schema}.file_tags ft ON ft.tag_id = t.id
+                        LEFT JOIN {schema}.files f ON f.id = ft.file_id AND f.missing = 0
+                        GROUP BY t.id"
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" UNION ALL ");
+        let mut query = self.connection.prepare(&format!(
+            "SELECT name, sum(files) FROM ({counts}) GROUP BY name ORDER BY name"
+        ))?;
+
+        Ok(query
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?)
     }
 
     pub fn delete_tags(&mut self, names: &[&str]) -> Result<(), DatabaseError> {
-        let tx = self.connection.transaction()?;
-        for name in names {
-            let Some(tag) = get_tag_by_name(&tx, name)? else {
-                return Err(DatabaseError::NoSuchTag(name.to_string()));
-            };
-            delete_tag(&tx, tag.id)?;
+        self.write_transaction(|tx| {
+            for name in names {
+                let Some(tag) = get_tag_by_name(tx, name)? else {
+                    return Err(DatabaseError::NoSuchTag(name.to_string()));
+                };
+                delete_tag(tx, tag.id)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Adds tags to file matched by fingerprint, file is stored when it has no tags yet
+pub fn tag_file(tx: &Transaction, file: &File, tag_names: &[&str]) -> Result<(), DatabaseError> {
+    let file_id = match get_file_id(tx, &file.fingerprint_hash)? {
+        Some(file_id) => {
+            fill_mime_type(tx, file_id, file.mime_type.as_deref())?;
+            file_id
         }
+        None => create_file(tx, file)?.id,
+    };
+    debug!("file_id: {file_id}");
+
+    let file_tag_ids = get_file_tag_ids_by_id(tx, file_id)?;
+    for tag_name in tag_names {
+        let tag_id = get_or_create_tag_id(tx, tag_name.trim())?;
+        debug!("tag_id: {tag_id}");
 
-        tx.commit()?;
-        Ok(())
+        if !file_tag_ids.contains(&tag_id) {
+            reference_file_tag(tx, file_id, tag_id)?;
+        }
     }
+
+    Ok(())
+}
+
+pub fn get_or_create_tag_id(tx: &Transaction, name: &str) -> Result<i32, DatabaseError> {
+    get_tag_id_by_name(tx, name)?.map_or_else(
+        || {
+            if name.starts_with(TYPE_FACET_PREFIX) {
+                return Err(DatabaseError::ReservedTagName(name.into()));
+            }
+            let tag_id = create_tag(tx, name).map(|tag| tag.id);
+            info!("created tag: {name}");
+            tag_id
+        },
+        Ok,
+    )
 }
 
 pub fn get_tag_by_name(conn: &Connection, name: &str) -> Result<Option<DbTag>, DatabaseError> {
@@ -80,6 +124,13 @@ pub fn get_tag_by_name(conn: &Connection, name: &str) -> Result<Option<DbTag>, D
         .optional()?)
 }
 
+pub fn delete_all_tags(tx: &Transaction) -> Result<(), DatabaseError> {
+    tx.execute("DELETE FROM tags", [])?;
+    debug!("deleted all tags");
+
+    Ok(())
+}
+
 fn delete_tag(tx: &Transaction, id: i32) -> Result<(), DatabaseError> {
     tx.execute(
         "DELETE FROM tags
@@ -91,6 +142,24 @@ fn delete_tag(tx: &Transaction, id: i32) -> Result<(), DatabaseError> {
     Ok(())
 }
 
+/// Deletes tag unless some file still has it, returns name of deleted tag
+pub fn delete_tag_if_unused(tx: &Transaction, id: i32) -> Result<Option<String>, DatabaseError> {
+    let name = tx
+        .query_row(
+            "DELETE FROM tags
+                WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM file_tags WHERE tag_id = ?1)
+                RETURNING name",
+            (id,),
+            |row| row.get(0),
+        )
+        .optional()?;
+    if name.is_some() {
+        debug!("deleted unused tag with id: {id}");
+    }
+
+    Ok(name)
+}
+
 fn create_tag(tx: &Transaction, name: &str) -> Result<DbTag, DatabaseError> {
     let mut insert = tx.prepare(
         "INSERT INTO tags (name) 
@@ -109,8 +178,13 @@ fn create_tag(tx: &Transaction, name: &str) -> Result<DbTag, DatabaseError> {
     Ok(db_tag)
 }
 
-fn get_tag_names(conn: &Connection) -> Result<Vec<String>, DatabaseError> {
-    let mut query = conn.prepare("SELECT name FROM tags")?;
+pub fn get_tag_names(conn: &Connection, schemas: &[String]) -> Result<Vec<String>, DatabaseError> {
+    let query = schemas
+        .iter()
+        .map(|schema| format!("SELECT name FROM {schema}.tags"))
+        .collect::<Vec<_>>()
+        .join(" UNION ");
+    let mut query = conn.prepare(&query)?;
 
     Ok(query
         .query_map([], |row| row.get(0))?
@@ -118,7 +192,7 @@ fn get_tag_names(conn: &Connection) -> Result<Vec<String>, DatabaseError> {
         .collect())
 }
 
-fn get_tag_id_by_name(conn: &Connection, name: &str) -> Result<Option<i32>, DatabaseError> {
+pub fn get_tag_id_by_name(conn: &Connection, name: &str) -> Result<Option<i32>, DatabaseError> {
     let mut query = conn.prepare(
         "SELECT id FROM tags 
              WHERE name = ?1",
@@ -0,0 +1,125 @@
+use crate::db::DatabaseError;
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Value id used by TMSU for tags without value
+const NO_VALUE_ID: i64 = 0;
+/// Separates tag name from its value, e.g. `year=2017`
+const TAG_VALUE_SEPARATOR: char = '=';
+
+/// Contents of a TMSU database, read-only
+#[derive(Debug, Default)]
+pub struct TmsuDatabase {
+    pub files: Vec<TmsuFile>,
+    /// Tag implications as `(tag, implied tag)`, anytaggy has no equivalent of them
+    pub implications: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct TmsuFile {
+    /// Absolute path, paths relative to TMSU root are resolved against it
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Tags with values are joined as `tag=value`
+    pub tags: Vec<String>,
+}
+
+/// Reads files, tags, values and implications from TMSU database, e.g. `.tmsu/db`
+pub fn read_tmsu_database(database_path: &Path) -> Result<TmsuDatabase, DatabaseError> {
+    let connection = Connection::open_with_flags(
+        database_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    let tables: i64 = connection.query_one(
+        "SELECT COUNT(*) FROM sqlite_schema
+            WHERE type = 'table' AND name IN ('file', 'tag', 'value', 'file_tag', 'implication')",
+        [],
+        |row| row.get(0),
+    )?;
+    if tables != 5 {
+        return Err(DatabaseError::NotTmsu);
+    }
+
+    // TMSU stores paths under its root relative to the directory containing `.tmsu`,
+    // canonical path makes it known for relative `db` or `.tmsu/db` as well
+    let database_path = database_path
+        .canonicalize()
+        .map_err(DatabaseError::TmsuPath)?;
+    let root = database_path
+        .parent()
+        .and_then(Path::parent)
+        .unwrap_or(Path::new("."));
+    debug!("tmsu root: {}", root.display());
+
+    let mut query = connection.prepare(
+        "SELECT id, directory, name, is_dir
+            FROM file
+            ORDER BY directory, name",
+    )?;
+    let files = query
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                PathBuf::from(row.get::<_, String>(1)?).join(row.get::<_, String>(2)?),
+                row.get::<_, bool>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut tags_query = connection.prepare(
+        "SELECT tag.name, file_tag.value_id, value.name
+            FROM file_tag
+            JOIN tag ON tag.id = file_tag.tag_id
+            LEFT JOIN value ON value.id = file_tag.value_id
+            WHERE file_tag.file_id = ?1
+            ORDER BY tag.name, value.name",
+    )?;
+    let files = files
+        .into_iter()
+        .map(|(id, path, is_dir)| {
+            let tags = tags_query
+                .query_map([id], |row| {
+                    Ok(tag_with_value(row.get(0)?, row.get(1)?, row.get(2)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(TmsuFile {
+                // Drops `.` used by TMSU as directory of files in its root
+                path: root.join(path).components().collect(),
+                is_dir,
+                tags,
+            })
+        })
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+    let mut query = connection.prepare(
+        "SELECT tag.name, implication.value_id, value.name,
+                implied_tag.name, implication.implied_value_id, implied_value.name
+            FROM implication
+            JOIN tag ON tag.id = implication.tag_id
+            LEFT JOIN value ON value.id = implication.value_id
+            JOIN tag AS implied_tag ON implied_tag.id = implication.implied_tag_id
+            LEFT JOIN value AS implied_value ON implied_value.id = implication.implied_value_id
+            ORDER BY tag.name, implied_tag.name",
+    )?;
+    let implications = query
+        .query_map([], |row| {
+            Ok((
+                tag_with_value(row.get(0)?, row.get(1)?, row.get(2)?),
+                tag_with_value(row.get(3)?, row.get(4)?, row.get(5)?),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(TmsuDatabase {
+        files,
+        implications,
+    })
+}
+
+fn tag_with_value(tag: String, value_id: i64, value: Option<String>) -> String {
+    match value {
+        Some(value) if value_id != NO_VALUE_ID => format!("{tag}{TAG_VALUE_SEPARATOR}{value}"),
+        _ => tag,
+    }
+}
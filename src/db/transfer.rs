@@ -0,0 +1,344 @@
+use crate::db::{
+    Database, DatabaseError, File,
+    tables::{
+        file_tags::{
+            get_file_tag_ids_by_id, get_file_tag_names_by_id, get_file_xmp_tags_by_id,
+            reference_file_tag, set_file_tag_from_xmp, unreference_file_tag,
+        },
+        files::{
+            create_file, delete_all_files, delete_file, fill_mime_type, find_file, get_all_files,
+            untag_file,
+        },
+        tags::{
+            delete_all_tags, delete_tag_if_unused, get_or_create_tag_id, get_tag_names, tag_file,
+        },
+    },
+};
+use rusqlite::{Connection, Transaction};
+use tracing::debug;
+
+/// File along with all of its tags
+#[derive(Debug, Clone)]
+pub struct TaggedFile {
+    pub file: File,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportMode {
+    /// Add imported files and tags to existing ones
+    Merge,
+    /// Remove everything from database before importing
+    Replace,
+    /// Report what merge would change, without changing anything
+    DryRun,
+}
+
+/// Changes made by import, paths are the ones stored in database
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Files which were not in database
+    pub added: Vec<String>,
+    /// Files with changed tags, along with tags added and removed
+    pub updated: Vec<(String, Vec<String>, Vec<String>)>,
+    /// Count of files which already had all of the tags
+    pub unchanged: usize,
+    /// Tags missing in imported document, deleted when database is replaced,
+    /// or left without files once their keyword is gone from XMP sidecars
+    pub deleted_tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MergePreference {
+    /// Keep files and tags missing in their database
+    Ours,
+    /// Remove files and tags missing in their database
+    Theirs,
+}
+
+/// Changes made by merge, paths are the ones stored in our database
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// Files only in their database, added to ours
+    pub only_theirs: Vec<String>,
+    /// Files only in our database, removed when their database is preferred
+    pub only_ours: Vec<String>,
+    /// Files in both databases with tags added and removed by merge
+    pub updated: Vec<(String, Vec<String>, Vec<String>)>,
+    /// Count of files which have the same tags in both databases
+    pub unchanged: usize,
+    /// Tags left without files once their database is preferred
+    pub deleted_tags: Vec<String>,
+}
+
+/// Tags to add to and remove from the file
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    pub file: File,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Database {
+    /// Files with their tags, sorted by path.
+    /// Only the main database is used
+    pub fn get_tagged_files(&self) -> Result<Vec<TaggedFile>, DatabaseError> {
+        get_tagged_files(&self.connection)
+    }
+
+    /// Imports files with tags in a single transaction.
+    /// Files are matched by path first, then by contents hash
+    pub fn import_files(
+        &mut self,
+        tags: &[String],
+        files: &[TaggedFile],
+        mode: ImportMode,
+    ) -> Result<ImportReport, DatabaseError> {
+        self.import(tags, files, mode, false)
+    }
+
+    /// Same as [`Database::import_files`] for keywords of XMP sidecars.
+    /// Tags added by previous imports are removed when the keyword is gone from the sidecar
+    pub fn import_xmp_keywords(
+        &mut self,
+        tags: &[String],
+        files: &[TaggedFile],
+        mode: ImportMode,
+    ) -> Result<ImportReport, DatabaseError> {
+        self.import(tags, files, mode, true)
+    }
+
+    fn import(
+        &mut self,
+        tags: &[String],
+        files: &[TaggedFile],
+        mode: ImportMode,
+        from_xmp: bool,
+    ) -> Result<ImportReport, DatabaseError> {
+        self.retried_transaction(mode != ImportMode::DryRun, |tx| {
+            let mut report = ImportReport::default();
+            if mode == ImportMode::Replace {
+                report.deleted_tags = get_tag_names(tx, &["main".into()])?
+                    .into_iter()
+                    .filter(|tag| !tags.contains(tag))
+                    .collect();
+                delete_all_files(tx)?;
+                delete_all_tags(tx)?;
+            }
+
+            let existing_tags = get_tag_names(tx, &["main".into()])?;
+            for tag in tags {
+                if !existing_tags.contains(tag) {
+                    get_or_create_tag_id(tx, tag)?;
+                }
+            }
+
+            for tagged_file in files {
+                import_file(tx, tagged_file, from_xmp, &mut report)?;
+            }
+            debug!("import report: {report:?}");
+
+            Ok(report)
+        })
+    }
+
+    /// Merges files with tags from other database in a single transaction.
+    /// Files are matched by path first, then by contents hash, tags of matched files are united
+    /// unless their database is preferred
+    pub fn merge_files(
+        &mut self,
+        files: &[TaggedFile],
+        prefer: MergePreference,
+    ) -> Result<MergeReport, DatabaseError> {
+        self.write_transaction(|tx| {
+            let mut report = MergeReport::default();
+            let mut matched_ids = Vec::new();
+            let mut unreferenced_tag_ids = Vec::new();
+            for tagged_file in files {
+                merge_file(
+                    tx,
+                    tagged_file,
+                    prefer,
+                    &mut matched_ids,
+                    &mut unreferenced_tag_ids,
+                    &mut report,
+                )?;
+            }
+
+            for db_file in get_all_files(tx)? {
+                if matched_ids.contains(&db_file.id) {
+                    continue;
+                }
+                if prefer == MergePreference::Theirs {
+                    unreferenced_tag_ids.extend(get_file_tag_ids_by_id(tx, db_file.id)?);
+                    delete_file(tx, db_file.id)?;
+                }
+                report.only_ours.push(db_file.path);
+            }
+            // Tags dropped by the merge are removed along with the last file having them
+            for tag_id in unreferenced_tag_ids {
+                report
+                    .deleted_tags
+                    .extend(delete_tag_if_unused(tx, tag_id)?);
+            }
+            debug!("merge report: {report:?}");
+
+            Ok(report)
+        })
+    }
+
+    /// Applies all of the edits in a single transaction, nothing is changed if any of them fails.
+    /// Files are matched by path first, then by contents hash, files left without tags are removed
+    pub fn edit_files(&mut self, edits: &[FileEdit]) -> Result<(), DatabaseError> {
+        self.write_transaction(|tx| {
+            for edit in edits {
+                edit_file(tx, edit)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+pub(super) fn get_tagged_files(conn: &Connection) -> Result<Vec<TaggedFile>, DatabaseError> {
+    get_all_files(conn)?
+        .into_iter()
+        .map(|db_file| {
+            Ok(TaggedFile {
+                tags: get_file_tag_names_by_id(conn, db_file.id)?,
+                file: File {
+                    path: db_file.path,
+                    name: db_file.name,
+                    contents_hash: db_file.contents_hash,
+                    fingerprint_hash: db_file.fingerprint_hash,
+                    mime_type: db_file.mime_type,
+                },
+            })
+        })
+        .collect()
+}
+
+fn import_file(
+    tx: &Transaction,
+    tagged_file: &TaggedFile,
+    from_xmp: bool,
+    report: &mut ImportReport,
+) -> Result<(), DatabaseError> {
+    let file = &tagged_file.file;
+    let found = find_file(tx, &file.path, &file.contents_hash)?;
+    // Files without tags are never stored
+    if tagged_file.tags.is_empty() && (found.is_none() || !from_xmp) {
+        return Ok(());
+    }
+
+    let (file_id, path, is_new) = if let Some((file_id, path)) = found {
+        fill_mime_type(tx, file_id, file.mime_type.as_deref())?;
+        (file_id, path, false)
+    } else {
+        (create_file(tx, file)?.id, file.path.clone(), true)
+    };
+
+    let mut file_tag_ids = get_file_tag_ids_by_id(tx, file_id)?;
+    let mut added_tags = Vec::new();
+    for tag in &tagged_file.tags {
+        let tag_id = get_or_create_tag_id(tx, tag)?;
+        if !file_tag_ids.contains(&tag_id) {
+            reference_file_tag(tx, file_id, tag_id)?;
+            if from_xmp {
+                set_file_tag_from_xmp(tx, file_id, tag_id)?;
+            }
+            file_tag_ids.push(tag_id);
+            added_tags.push(tag.clone());
+        }
+    }
+    let mut removed_tags = Vec::new();
+    if from_xmp {
+        for (tag_id, tag) in get_file_xmp_tags_by_id(tx, file_id)? {
+            if !tagged_file.tags.contains(&tag) {
+                unreference_file_tag(tx, file_id, tag_id)?;
+                report
+                    .deleted_tags
+                    .extend(delete_tag_if_unused(tx, tag_id)?);
+                file_tag_ids.retain(|id| *id != tag_id);
+                removed_tags.push(tag);
+            }
+        }
+        if file_tag_ids.is_empty() {
+            delete_file(tx, file_id)?;
+        }
+    }
+
+    if is_new {
+        report.added.push(path);
+    } else if added_tags.is_empty() && removed_tags.is_empty() {
+        report.unchanged += 1;
+    } else {
+        report.updated.push((path, added_tags, removed_tags));
+    }
+
+    Ok(())
+}
+
+fn merge_file(
+    tx: &Transaction,
+    tagged_file: &TaggedFile,
+    prefer: MergePreference,
+    matched_ids: &mut Vec<i32>,
+    unreferenced_tag_ids: &mut Vec<i32>,
+    report: &mut MergeReport,
+) -> Result<(), DatabaseError> {
+    let file = &tagged_file.file;
+    let Some((file_id, path)) = find_file(tx, &file.path, &file.contents_hash)? else {
+        let file_id = create_file(tx, file)?.id;
+        for tag in &tagged_file.tags {
+            reference_file_tag(tx, file_id, get_or_create_tag_id(tx, tag)?)?;
+        }
+        matched_ids.push(file_id);
+        report.only_theirs.push(file.path.clone());
+        return Ok(());
+    };
+    matched_ids.push(file_id);
+    fill_mime_type(tx, file_id, file.mime_type.as_deref())?;
+
+    let file_tag_names = get_file_tag_names_by_id(tx, file_id)?;
+    let mut added_tags = Vec::new();
+    for tag in &tagged_file.tags {
+        if !file_tag_names.contains(tag) {
+            reference_file_tag(tx, file_id, get_or_create_tag_id(tx, tag)?)?;
+            added_tags.push(tag.clone());
+        }
+    }
+    let mut removed_tags = Vec::new();
+    if prefer == MergePreference::Theirs {
+        for tag in file_tag_names {
+            if !tagged_file.tags.contains(&tag) {
+                let tag_id = get_or_create_tag_id(tx, &tag)?;
+                unreference_file_tag(tx, file_id, tag_id)?;
+                unreferenced_tag_ids.push(tag_id);
+                removed_tags.push(tag);
+            }
+        }
+    }
+
+    if added_tags.is_empty() && removed_tags.is_empty() {
+        report.unchanged += 1;
+    } else {
+        report.updated.push((path, added_tags, removed_tags));
+    }
+
+    Ok(())
+}
+
+fn edit_file(tx: &Transaction, edit: &FileEdit) -> Result<(), DatabaseError> {
+    let added: Vec<&str> = edit.added.iter().map(String::as_str).collect();
+    let removed: Vec<&str> = edit.removed.iter().map(String::as_str).collect();
+    if !added.is_empty() {
+        tag_file(tx, &edit.file, &added)?;
+    }
+    if !removed.is_empty() {
+        untag_file(tx, &edit.file, &removed)?;
+    }
+    debug!("edited file {}: {edit:?}", edit.file.path);
+
+    Ok(())
+}
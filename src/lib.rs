@@ -1,13 +1,22 @@
 mod commands;
+pub mod config;
 pub mod db;
 
-use crate::db::{Database, DatabaseMode};
+use crate::{
+    config::{CONFIG_ENV, Config, resolve_database_path},
+    db::{Database, DatabaseMode, ImportMode, MergePreference, Setting},
+};
 use clap::{Parser, Subcommand, builder::NonEmptyStringValueParser};
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use thiserror::Error;
 use tracing::{debug, error};
 
 pub const DATABASE_FILENAME: &str = ".anytaggy.db";
+pub const DATABASE_ENV: &str = "ANYTAGGY_DB";
+pub const BUSY_TIMEOUT_ENV: &str = "ANYTAGGY_BUSY_TIMEOUT";
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -15,6 +24,8 @@ pub enum AppError {
     FileIsNotUnderDatabase,
     #[error("Specified database file could not be found")]
     DatabaseNotFound,
+    #[error("Database already exists: {0}")]
+    DatabaseAlreadyExists(PathBuf),
     #[error("Couldn't retrieve file name from path")]
     NoFileNameFromPath,
     #[error("No tags specified")]
@@ -23,16 +34,127 @@ pub enum AppError {
     FileOutsideStructure,
     #[error("Could not find specified file")]
     FileNotFound,
+    #[error("Invalid value of {BUSY_TIMEOUT_ENV}, expected milliseconds: {0}")]
+    InvalidBusyTimeout(String),
+    #[error("Invalid config file: {0}")]
+    InvalidConfig(String),
+    #[error("Database named '{0}' is not defined in config file")]
+    UnknownNamedDatabase(String),
+    #[error("Invalid exported document: {0}")]
+    InvalidDocument(String),
+    #[error("Unsupported exported document version: {0}")]
+    UnsupportedDocumentVersion(u32),
+    #[error("Invalid auto-tagging rules: {0}")]
+    InvalidRules(String),
+    #[error("No auto-tagging rules found in '.anytaggy-rules.toml' of database root")]
+    NoRules,
+    #[error("View directory is not empty: {0}")]
+    ViewDirectoryNotEmpty(PathBuf),
+    #[error("Directory is not a view: {0}")]
+    NotAView(PathBuf),
+    #[error("Invalid view state: {0}")]
+    InvalidView(String),
+    #[error("Could not watch files: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("Watching files stopped unexpectedly")]
+    WatchStopped,
+    #[error("Could not start server: {0}")]
+    Serve(String),
+    #[error("Server is read-only")]
+    ReadOnlyServer,
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Unknown endpoint: {0}")]
+    UnknownEndpoint(String),
+    #[error("Request body is larger than {0} bytes")]
+    RequestTooLarge(u64),
+    #[error("Could not serialize response: {0}")]
+    SerializeResponse(String),
+    #[error("Host '{0}' is not allowed, use the listen address or localhost")]
+    HostNotAllowed(String),
+    #[error("Request body must be sent as application/json")]
+    UnsupportedContentType,
+    #[error("Tag names could not be blank or contain ',': '{0}'")]
+    InvalidTagName(String),
+    #[error("Terminal interface needs an interactive terminal")]
+    NotATerminal,
+    #[error("No command specified, pass it after '--'")]
+    NoCommandSpecified,
+    #[error("Command failed for {0} of {1} invocations")]
+    ExecFailed(usize, usize),
+    #[error("No dynamic completions for this argument")]
+    NoCompletions,
+    #[error("Invalid line {0} of edited list: {1}")]
+    InvalidEdit(usize, String),
+    #[error("Editor '{0}' failed, nothing was changed")]
+    EditorFailed(String),
+    #[error("Invalid XMP sidecar {0}: {1}")]
+    InvalidXmp(PathBuf, String),
     #[error("Database error: {0}")]
     Database(#[from] db::DatabaseError),
     #[error("Unhandled error: {0}")]
     Unhandled(#[from] std::io::Error),
 }
 
+impl AppError {
+    /// Suggestion on how user could resolve the error
+    #[must_use]
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AppError::DatabaseNotFound => Some(
+                "Create database with 'anytaggy init', run command inside a directory with database or specify it with '--database-path'",
+            ),
+            AppError::Database(db::DatabaseError::Locked) => {
+                Some("Another process is writing to the database, try again later")
+            }
+            AppError::Database(db::DatabaseError::Corrupt) => {
+                Some("Restore the database from a backup or remove it to start over")
+            }
+            AppError::Database(db::DatabaseError::ReadOnly) => {
+                Some("Check permissions of the database file and its directory")
+            }
+            AppError::ReadOnlyServer => Some("Start server with 'anytaggy serve --writable'"),
+            AppError::NotAView(_) => Some("Create view with 'anytaggy view build'"),
+            AppError::NoRules => Some(
+                "Add rules like '[[rules]] name = \"photos\" tags = [\"photo\"] extensions = [\"jpg\"]', each on its own line",
+            ),
+            AppError::Database(db::DatabaseError::InvalidTextSidecar(_)) => {
+                Some("Each line should be a file path and comma separated tags, divided by a tab")
+            }
+            AppError::Database(db::DatabaseError::NotTmsu) => {
+                Some("TMSU database is usually stored as '.tmsu/db' in the root of tagged tree")
+            }
+            AppError::Database(db::DatabaseError::Migration(_)) => {
+                Some("Database was left unchanged, make a backup of it before retrying")
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(after_help = format!("Database is picked in following order:
+  1. --database-path option
+  2. {DATABASE_ENV} environment variable
+  3. Database configured for current directory in config file
+  4. '{DATABASE_FILENAME}' found in current or parent directories
+  5. Default database from config file
+
+Config file is read from '$XDG_CONFIG_HOME/anytaggy/config.toml' (or '~/.config/anytaggy/config.toml'):
+  default = \"~/.anytaggy.db\"
+  [databases]
+  photos = \"/mnt/photos/.anytaggy.db\"
+  [[directories]]
+  path = \"/mnt/photos\"
+  database = \"@photos\"
+
+Environment variables:
+  {DATABASE_ENV}  Path to database or @name of database from config file
+  {CONFIG_ENV}  Path to config file
+  {BUSY_TIMEOUT_ENV}  How long to wait for other processes writing to the database, in milliseconds. Defaults to {}", db::DEFAULT_BUSY_TIMEOUT.as_millis()))]
 pub struct Args {
-    #[arg(short, long, help = format!("Path to database to store tags, files metadata. Defaults to '{DATABASE_FILENAME}'"))]
+    #[arg(short, long, env = DATABASE_ENV, help = format!("Path to database to store tags, files metadata, or @name of database from config file. Defaults to '{DATABASE_FILENAME}' found in current or parent directories. Directory containing database is the root of tagged files, file paths are stored relative to it"))]
     pub database_path: Option<PathBuf>,
 
     #[command(subcommand)]
@@ -41,8 +163,15 @@ pub struct Args {
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
+    /// Create new database.
+    /// Directory with the database becomes root for all tagged files
+    Init {
+        /// Directory to create database in.
+        /// If not specified, uses current directory
+        directory: Option<PathBuf>,
+    },
     /// Tagging files with tag names.
-    /// Creates database, tags inside database if required
+    /// Creates tags inside database if required
     Tag {
         /// Path to the file
         file_path: PathBuf,
@@ -50,6 +179,10 @@ pub enum Command {
         /// Tags to add to the file
         #[arg(short, long, value_parser = NonEmptyStringValueParser::new(), value_delimiter=',')]
         tags: Vec<String>,
+
+        /// Create database in current directory if it could not be found
+        #[arg(long)]
+        create: bool,
     },
     /// Untag files from database.
     /// Does not delete tags, only un-references files from them
@@ -73,56 +206,415 @@ pub enum Command {
         /// Path to the file with tags.
         /// If not specified, lists all tags from database
         file_path: Option<PathBuf>,
+
+        #[command(flatten)]
+        sources: DatabaseSources,
     },
     /// List files
     Files {
-        /// Tags to list files with.
+        /// Tags to list files with, 'type:image' or 'type:image/jpeg' selects files by type
+        /// sniffed from their contents.
         /// If not specified, lists all files from database
         #[arg(value_parser = NonEmptyStringValueParser::new(), value_delimiter=' ')]
         tags: Option<Vec<String>>,
+
+        #[command(flatten)]
+        sources: DatabaseSources,
+    },
+    /// Run command for each file with all of the tags, like 'anytaggy exec "photo 2024" -- cp {} backup/'.
+    /// Every '{}' in arguments is replaced with absolute file path, path is appended when there are none.
+    /// Exits with failure if any invocation fails
+    Exec {
+        /// Tags to match files by, like in 'files'
+        #[arg(value_parser = NonEmptyStringValueParser::new(), value_delimiter=' ', required = true)]
+        tags: Vec<String>,
+
+        /// Run command with as many paths at once as fit in 128 KiB of arguments, like xargs.
+        /// Standalone '{}' expands to all of them
+        #[arg(long)]
+        batch: bool,
+
+        /// Command to run and its arguments
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+
+        #[command(flatten)]
+        sources: DatabaseSources,
+    },
+    /// Export files and tags from database.
+    /// Paths are relative to database root
+    Export {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+    /// Import files and tags exported with 'export'.
+    /// Files are matched by path, then by contents hash
+    Import {
+        /// Path to exported document, '-' reads it from stdin
+        document_path: PathBuf,
+
+        #[arg(short, long, value_enum, default_value_t = ImportMode::Merge)]
+        mode: ImportMode,
+    },
+    /// Tag files with auto-tagging rules from '.anytaggy-rules.toml' in database root.
+    /// Reports which rule produced which tag
+    Autotag {
+        /// Files or directories to tag, directories are searched recursively.
+        /// Defaults to database root
+        paths: Vec<PathBuf>,
+
+        /// Report matching rules without tagging files
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Extract EXIF of images and tags of audio files into 'exif:' and 'audio:' tags,
+    /// e.g. 'exif:camera=...' or 'audio:artist=...'.
+    /// Metadata is extracted again when contents of the file change
+    Extract {
+        /// Files or directories to extract metadata from, directories are searched recursively.
+        /// Defaults to database root
+        paths: Vec<PathBuf>,
+    },
+    /// Import files and tags from TMSU database.
+    /// Tag values are imported as 'tag=value', implications are not supported
+    ImportTmsu {
+        /// Path to TMSU database, e.g. '.tmsu/db'
+        tmsu_database_path: PathBuf,
+
+        #[arg(short, long, value_enum, default_value_t = ImportMode::Merge)]
+        mode: ImportMode,
+    },
+    /// Merge files and tags from other database in a single transaction.
+    /// Files are matched by path, then by contents hash
+    Merge {
+        /// Path to other database
+        other_database_path: PathBuf,
+
+        /// Which database wins when file or tag is missing in one of them
+        #[arg(long, value_enum, default_value_t = MergePreference::Ours)]
+        prefer: MergePreference,
+    },
+    /// Replace files and tags in database with ones from text sidecar,
+    /// e.g. after merging it with version control
+    RebuildFromText {
+        /// Path to text sidecar. Defaults to the one next to database
+        text_path: Option<PathBuf>,
+    },
+    /// Mirror tags to and from 'user.xdg.tags' extended attribute of files
+    Xattr {
+        #[command(subcommand)]
+        command: XattrCommand,
+    },
+    /// Write tags to and read them from XMP sidecars, e.g. 'photo.jpg.xmp'
+    Xmp {
+        #[command(subcommand)]
+        command: XmpCommand,
+    },
+    /// Follow changes of files under database root: moved files keep their tags,
+    /// deleted ones are marked missing and modified ones are hashed again
+    Watch {
+        /// Stop watching after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// List files marked missing by 'watch', which are left out of other commands
+    /// until they are back
+    Missing {
+        /// Remove missing files from database, their tags are kept
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Serve JSON API over HTTP: `GET /tags`, `GET /files?tags=a,b`, `GET /file?path=p`,
+    /// `POST /file/tags?path=p` with JSON array of tags and `DELETE /file/tags?path=p&tags=a,b`.
+    /// File paths are relative to database root
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+
+        /// Allow tagging and untagging files, server is read-only by default
+        #[arg(long)]
+        writable: bool,
+    },
+    /// Answer line-delimited JSON-RPC 2.0 requests and batches from stdin on stdout, keeping database open.
+    /// Methods are `tags`, `files {tags}`, `file {path}`, `tag {path, tags}` and `untag {path, tags}`
+    Rpc,
+    /// Edit tags of files in editor, one 'path<TAB>tag1,tag2' line per file.
+    /// Changes are applied at once when editor is closed, removed lines leave files unchanged
+    Edit {
+        /// Files or directories to edit, or tags to query files by when some of them are not paths.
+        /// All tagged files are listed by default
+        targets: Vec<String>,
+
+        /// Editor command, defaults to $VISUAL, $EDITOR or vi
+        #[arg(long)]
+        editor: Option<String>,
+    },
+    /// Print shell completion script, like 'source <(anytaggy completions bash)'.
+    /// Tag names are completed from the database found when completing,
+    /// 'untag' completes only tags of the given file
+    Completions {
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+    /// Complete word at index of command line words, used by completion scripts
+    #[command(name = commands::completions::COMPLETE_COMMAND, hide = true)]
+    Complete {
+        index: usize,
+
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+    /// Browse tags and files matching a query in terminal, and tag or untag selected files
+    Tui,
+    /// Directories of symlinks to files matching a query, for apps unaware of tags
+    View {
+        #[command(subcommand)]
+        command: ViewCommand,
+    },
+    /// Mount tags as directories of links to files, `mkdir` creates a tag
+    /// and removing a link untags the file from the last tag of the path.
+    /// Blocks until the filesystem is unmounted
+    #[cfg(feature = "fuse")]
+    Mount { mountpoint: PathBuf },
+    /// Manage database itself
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Other databases to query along with the main one
+#[derive(clap::Args, Debug, Default)]
+pub struct DatabaseSources {
+    /// Additional database to query, could be specified multiple times.
+    /// Paths in results are resolved against root of the database they are stored in
+    #[arg(long = "database", value_name = "DATABASE_PATH")]
+    pub databases: Vec<PathBuf>,
+
+    /// Also query all databases found in this directory and its subdirectories
+    #[arg(long, value_name = "DIRECTORY")]
+    pub discover: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum XattrCommand {
+    /// Write tags of every file in database to its extended attribute
+    Export,
+    /// Read tags from extended attribute of files into database
+    Import {
+        /// Files or directories to read, directories are searched recursively.
+        /// Defaults to database root
+        paths: Vec<PathBuf>,
+
+        #[arg(short, long, value_enum, default_value_t = ImportMode::Merge)]
+        mode: ImportMode,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum XmpCommand {
+    /// Write tags of every file in database as 'dc:subject' keywords of its XMP sidecar.
+    /// Other contents of existing sidecars are kept
+    Export,
+    /// Read 'dc:subject' keywords from XMP sidecars into tags of files they describe
+    Import {
+        /// Sidecars or directories to read, directories are searched recursively.
+        /// Defaults to database root
+        paths: Vec<PathBuf>,
+
+        #[arg(short, long, value_enum, default_value_t = ImportMode::Merge)]
+        mode: ImportMode,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ViewCommand {
+    /// Create directory of symlinks to files having all of the tags.
+    /// Query is stored in the directory, so it could be synced later
+    Build {
+        directory: PathBuf,
+
+        /// Tags to link files with, same as in 'files'
+        #[arg(required = true, value_parser = NonEmptyStringValueParser::new(), value_delimiter=' ')]
+        tags: Vec<String>,
     },
+    /// Link new matches of the view query and remove stale links.
+    /// Uses database the view was built from, unless database is specified
+    Sync { directory: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommand {
+    /// Upgrade database schema to the version supported by this binary
+    Upgrade,
+    /// Enable or disable database setting
+    Set {
+        setting: Setting,
+
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Show whether database setting is enabled
+    Get { setting: Setting },
 }
 
 #[allow(clippy::missing_errors_doc)]
 #[allow(clippy::too_many_lines)]
 pub fn entrypoint(args: Args) -> Result<Option<String>, AppError> {
-    let is_tag_subcommand = matches!(
-        args.command,
-        Command::Tag {
-            file_path: _,
-            tags: _
+    if let Command::Init { directory } = args.command {
+        let database_path = match (args.database_path, directory) {
+            (Some(database_path), _) => resolve_database_path(&database_path)?,
+            (None, Some(directory)) => directory.join(DATABASE_FILENAME),
+            (None, None) => std::env::current_dir()?.join(DATABASE_FILENAME),
+        };
+        return commands::init::init(&database_path);
+    }
+    if let Command::Completions { shell } = args.command {
+        return commands::completions::completions(shell);
+    }
+
+    let can_create_database = matches!(args.command, Command::Tag { create: true, .. });
+    let mut attached_paths = match &args.command {
+        Command::Tags { sources, .. }
+        | Command::Files { sources, .. }
+        | Command::Exec { sources, .. } => attached_database_paths(sources)?,
+        _ => Vec::new(),
+    };
+    let database_path = match (&args.command, args.database_path) {
+        // Views remember database they were built from
+        (
+            Command::View {
+                command: ViewCommand::Sync { directory },
+            },
+            None,
+        ) => Some(commands::view::view_database(directory)?),
+        (Command::Complete { words, .. }, database_path) => {
+            commands::completions::database_path_arg(words).or(database_path)
         }
-    );
-    let database_path = if let Some(database_path) = args.database_path {
-        // If database path was specified, and it is not a tag subcommand (can't create new database)
+        (_, database_path) => database_path,
+    };
+    let mut database_path = match find_database(database_path)? {
+        // If database was specified, but it doesn't exist and it's not allowed to create new database
         // Then error out as user error
-        if !database_path.is_file() && !is_tag_subcommand {
+        Some(database_path) if !database_path.is_file() && !can_create_database => {
             return Err(AppError::DatabaseNotFound);
         }
-
-        database_path
-    } else if let Some(database_path) = search_database_in_parent_folders() {
-        // Search database path from current and parent folders
-        database_path
-    } else if is_tag_subcommand {
-        // If it's a root and we still couldn't find database, check if it's a tag subcommand
-        //  and if true, assume initial path to be the right one (new database will be created)
-        std::env::current_dir()?.join(DATABASE_FILENAME)
-    } else {
+        Some(database_path) => database_path,
+        // If we still couldn't find database, but creation was requested
+        //  assume current directory to be the right one (new database will be created)
+        None if can_create_database => std::env::current_dir()?.join(DATABASE_FILENAME),
+        // If there are other databases to query, first of them becomes the main one
+        None if !attached_paths.is_empty() => attached_paths.remove(0),
         // If it's not found and database will not be created - error out
-        return Err(AppError::DatabaseNotFound);
+        None => return Err(AppError::DatabaseNotFound),
     };
     debug!("database_path: {}", database_path.display());
 
     let mode = match args.command {
-        Command::Tag { .. } => DatabaseMode::ReadWriteCreate,
-        Command::Untag { .. } | Command::RmTags { .. } => DatabaseMode::ReadWrite,
-        Command::Tags { .. } | Command::Files { .. } => DatabaseMode::Read,
+        Command::Tag { create: true, .. } => DatabaseMode::ReadWriteCreate,
+        Command::Tag { .. } | Command::Untag { .. } | Command::RmTags { .. } => {
+            DatabaseMode::ReadWrite
+        }
+        // Dry run is rolled back, but still needs to write
+        Command::Import { .. }
+        | Command::ImportTmsu { .. }
+        | Command::Autotag { .. }
+        | Command::Extract { .. }
+        | Command::Merge { .. }
+        | Command::RebuildFromText { .. }
+        | Command::Watch { .. }
+        | Command::Missing { prune: true }
+        | Command::Rpc
+        | Command::Tui
+        | Command::Edit { .. }
+        | Command::Serve { writable: true, .. }
+        | Command::Xattr {
+            command: XattrCommand::Import { .. },
+        }
+        | Command::Xmp {
+            command: XmpCommand::Import { .. },
+        } => DatabaseMode::ReadWrite,
+        #[cfg(feature = "fuse")]
+        Command::Mount { .. } => DatabaseMode::ReadWrite,
+        Command::Tags { .. }
+        | Command::Files { .. }
+        | Command::Missing { prune: false }
+        | Command::Exec { .. }
+        | Command::Complete { .. }
+        | Command::Export { .. }
+        | Command::View { .. }
+        | Command::Serve { .. }
+        | Command::Xattr {
+            command: XattrCommand::Export,
+        }
+        | Command::Xmp {
+            command: XmpCommand::Export,
+        }
+        | Command::Db {
+            command: DbCommand::Get { .. },
+        } => DatabaseMode::Read,
+        Command::Db {
+            command: DbCommand::Set { .. },
+        } => DatabaseMode::ReadWrite,
+        Command::Db {
+            command: DbCommand::Upgrade,
+        } => DatabaseMode::Upgrade,
+        Command::Init { .. } => unreachable!("database is initialized before"),
+        Command::Completions { .. } => unreachable!("completions are generated before"),
     };
-    let mut db = Database::new(&mode, &database_path)?;
+    let busy_timeout = busy_timeout()?;
+    let mut db = Database::new_with_busy_timeout(&mode, &database_path, busy_timeout)?;
+
+    match &args.command {
+        Command::Tag { file_path, .. } | Command::Untag { file_path, .. }
+            if db.get_setting(Setting::IncludeNested)? =>
+        {
+            // File is tagged in the innermost database containing it
+            if let Some(nested_path) = nested_database_path(&database_path, file_path)? {
+                debug!("nested_database_path: {}", nested_path.display());
+                db = Database::new_with_busy_timeout(&mode, &nested_path, busy_timeout)?;
+                database_path = nested_path;
+            }
+        }
+        Command::Tags { .. } | Command::Files { .. }
+            if db.get_setting(Setting::IncludeNested)? =>
+        {
+            discover_nested_databases(&database_root(&database_path)?, &mut attached_paths);
+        }
+        _ => {}
+    }
+
+    let mut database_roots = vec![database_root(&database_path)?];
+    let mut canonical_database_paths = vec![database_path.canonicalize()?];
+    for attached_path in attached_paths {
+        let canonical_path = attached_path.canonicalize()?;
+        if canonical_database_paths.contains(&canonical_path) {
+            continue;
+        }
+        db.attach(&attached_path)?;
+        database_roots.push(database_root(&attached_path)?);
+        canonical_database_paths.push(canonical_path);
+    }
+    debug!("database_roots: {database_roots:?}");
+    let database_root = &database_roots[0];
 
     match args.command {
-        Command::Tag { file_path, tags } => {
+        Command::Tag {
+            file_path, tags, ..
+        } => {
             if !file_path.exists() {
                 return Err(AppError::FileNotFound);
             }
@@ -137,6 +629,7 @@ pub fn entrypoint(args: Args) -> Result<Option<String>, AppError> {
 
             commands::tag::tag_file(
                 &mut db,
+                database_root,
                 &file_path,
                 &tags.iter().map(String::as_str).collect::<Vec<_>>(),
             )
@@ -157,22 +650,28 @@ pub fn entrypoint(args: Args) -> Result<Option<String>, AppError> {
 
             commands::untag::untag_file(
                 &mut db,
+                database_root,
                 &file_path,
                 &tags.iter().map(String::as_str).collect::<Vec<_>>(),
             )
             .map(|()| None)
         }
-        Command::Tags { file_path } => {
+        Command::Tags { file_path, .. } => {
             if let Some(file_path) = file_path {
                 if !file_path.exists() {
                     return Err(AppError::FileNotFound);
                 }
 
-                if !check_file_paths_for_subdirectory(&database_path, &file_path)? {
+                let mut is_file_in_databases = false;
+                for database_path in &canonical_database_paths {
+                    is_file_in_databases |=
+                        check_file_paths_for_subdirectory(database_path, &file_path)?;
+                }
+                if !is_file_in_databases {
                     return Err(AppError::FileOutsideStructure);
                 }
 
-                commands::tags::get_file_tags(&db, &file_path)
+                commands::tags::get_file_tags(&db, &database_roots, &file_path)
             } else {
                 commands::tags::get_all_tags(&db)
             }
@@ -188,23 +687,120 @@ pub fn entrypoint(args: Args) -> Result<Option<String>, AppError> {
             )
             .map(|()| None)
         }
-        Command::Files { tags } => {
+        Command::Exec {
+            tags,
+            batch,
+            command,
+            ..
+        } => commands::exec::exec(
+            &db,
+            &database_roots,
+            &tags.iter().map(String::as_str).collect::<Vec<_>>(),
+            &command,
+            batch,
+        ),
+        Command::Files { tags, .. } => {
             if let Some(tags) = tags {
                 if tags.is_empty() {
                     Err(AppError::NoTagsSpecified)
                 } else {
                     commands::files::get_file_paths(
                         &db,
+                        &database_roots,
                         &tags.iter().map(String::as_str).collect::<Vec<_>>(),
                     )
                 }
             } else {
-                commands::files::get_files(&db)
+                commands::files::get_files(&db, &database_roots)
             }
         }
+        Command::Export {
+            format: ExportFormat::Json,
+        } => commands::export::export_json(&db),
+        Command::Import {
+            document_path,
+            mode,
+        } => commands::import::import_json(&mut db, &document_path, mode),
+        Command::Autotag { paths, dry_run } => {
+            commands::autotag::autotag(&mut db, database_root, &paths, dry_run)
+        }
+        Command::Serve { listen, writable } => {
+            commands::serve::serve(&mut db, &database_roots, &listen, writable)
+        }
+        Command::Edit { targets, editor } => {
+            commands::edit::edit(&mut db, database_root, &targets, editor.as_deref())
+        }
+        Command::Complete { index, words } => {
+            commands::completions::complete(&db, &database_roots, index, &words)
+        }
+        Command::Tui => commands::tui::tui(&mut db, &database_roots),
+        Command::Rpc => commands::rpc::rpc(
+            &mut db,
+            &database_roots,
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+        ),
+        Command::Watch { timeout } => {
+            commands::watch::watch(&mut db, database_root, timeout, std::io::stdout())
+        }
+        Command::Missing { prune } => commands::missing::missing(&mut db, prune),
+        Command::Extract { paths } => commands::extract::extract(&mut db, database_root, &paths),
+        Command::ImportTmsu {
+            tmsu_database_path,
+            mode,
+        } => commands::import_tmsu::import_tmsu(&mut db, database_root, &tmsu_database_path, mode),
+        Command::Merge {
+            other_database_path,
+            prefer,
+        } => commands::merge::merge(&mut db, &other_database_path, prefer),
+        Command::RebuildFromText { text_path } => commands::rebuild_from_text::rebuild_from_text(
+            &mut db,
+            database_root,
+            &text_path.unwrap_or_else(|| db::text_sidecar_path(&database_path)),
+        ),
+        Command::Xattr {
+            command: XattrCommand::Export,
+        } => commands::xattr::export(&db, database_root),
+        Command::Xattr {
+            command: XattrCommand::Import { paths, mode },
+        } => commands::xattr::import(&mut db, database_root, &paths, mode),
+        Command::Xmp {
+            command: XmpCommand::Export,
+        } => commands::xmp::export(&db, database_root),
+        Command::View {
+            command: ViewCommand::Build { directory, tags },
+        } => commands::view::build(&db, &database_path, &database_roots, &directory, &tags),
+        Command::View {
+            command: ViewCommand::Sync { directory },
+        } => commands::view::sync(&db, &database_roots, &directory),
+        Command::Xmp {
+            command: XmpCommand::Import { paths, mode },
+        } => commands::xmp::import(&mut db, database_root, &paths, mode),
+        Command::Db {
+            command: DbCommand::Upgrade,
+        } => commands::db::upgrade(&mut db, database_root),
+        Command::Db {
+            command: DbCommand::Set { setting, enabled },
+        } => commands::db::set_setting(&mut db, setting, enabled),
+        Command::Db {
+            command: DbCommand::Get { setting },
+        } => commands::db::get_setting(&db, setting),
+        #[cfg(feature = "fuse")]
+        Command::Mount { mountpoint } => commands::mount::mount(db, database_roots, &mountpoint),
+        Command::Init { .. } => unreachable!("database is initialized before"),
+        Command::Completions { .. } => unreachable!("completions are generated before"),
     }
 }
 
+/// Directory containing the database, all file paths in database are relative to it
+fn database_root(database_path: &Path) -> Result<PathBuf, AppError> {
+    let parent = database_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    Ok(parent.canonicalize()?)
+}
+
 fn check_file_paths_for_subdirectory(parent: &Path, child: &Path) -> Result<bool, AppError> {
     if !parent.exists() || !child.exists() {
         return Ok(false); // Files don't exist, so they can't be subdirectories
@@ -225,6 +821,121 @@ fn check_file_paths_for_subdirectory(parent: &Path, child: &Path) -> Result<bool
     Ok(child.starts_with(parent_dir))
 }
 
+fn busy_timeout() -> Result<Duration, AppError> {
+    match std::env::var(BUSY_TIMEOUT_ENV) {
+        Ok(value) => value
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| AppError::InvalidBusyTimeout(value)),
+        Err(_) => Ok(db::DEFAULT_BUSY_TIMEOUT),
+    }
+}
+
+/// Picks database in order of precedence, described in `--help`
+/// Config file is only read when database wasn't specified or it's referenced by name
+fn find_database(database_path: Option<PathBuf>) -> Result<Option<PathBuf>, AppError> {
+    if let Some(database_path) = database_path {
+        return resolve_database_path(&database_path).map(Some);
+    }
+    let config = Config::load()?;
+    if let Some(database_path) = config.directory_database(&std::env::current_dir()?)? {
+        return Ok(Some(database_path));
+    }
+    if let Some(database_path) = search_database_in_parent_folders() {
+        return Ok(Some(database_path));
+    }
+    config.default_database()
+}
+
+/// Databases to query along with the main one, specified or discovered
+fn attached_database_paths(sources: &DatabaseSources) -> Result<Vec<PathBuf>, AppError> {
+    let mut database_paths = Vec::new();
+    for database_path in &sources.databases {
+        let database_path = resolve_database_path(database_path)?;
+        if !database_path.is_file() {
+            return Err(AppError::DatabaseNotFound);
+        }
+        database_paths.push(database_path);
+    }
+    if let Some(directory) = &sources.discover {
+        discover_databases(directory, &mut database_paths)?;
+    }
+    debug!("attached_database_paths: {database_paths:?}");
+
+    Ok(database_paths)
+}
+
+/// Recursively searches directory for databases, symlinked directories are not followed
+fn discover_databases(directory: &Path, database_paths: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    let mut entries = std::fs::read_dir(directory)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            discover_databases(&entry.path(), database_paths)?;
+        } else if file_type.is_file() && entry.file_name() == DATABASE_FILENAME {
+            database_paths.push(entry.path());
+        }
+    }
+
+    Ok(())
+}
+
+/// Searches directory for databases nested in it. Directories under a nested database
+/// belong to it and are not searched, unreadable and symlinked directories are skipped
+fn discover_nested_databases(directory: &Path, database_paths: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(directory)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+    {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("skipped {}: {err}", directory.display());
+            return;
+        }
+    };
+    let mut directories: Vec<PathBuf> = entries
+        .iter()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(std::fs::DirEntry::path)
+        .collect();
+    directories.sort();
+    for directory in directories {
+        let database_path = directory.join(DATABASE_FILENAME);
+        if database_path.is_file() {
+            database_paths.push(database_path);
+        } else {
+            discover_nested_databases(&directory, database_paths);
+        }
+    }
+}
+
+/// Searches for the innermost database containing the file, between file and database root
+fn nested_database_path(
+    database_path: &Path,
+    file_path: &Path,
+) -> Result<Option<PathBuf>, AppError> {
+    let root = database_root(database_path)?;
+    let Ok(mut directory) = file_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .canonicalize()
+    else {
+        // Missing file is reported later
+        return Ok(None);
+    };
+
+    while directory.starts_with(&root) && directory != root {
+        let nested_path = directory.join(DATABASE_FILENAME);
+        if nested_path.is_file() {
+            return Ok(Some(nested_path));
+        }
+        directory.pop();
+    }
+
+    Ok(None)
+}
+
 fn search_database_in_parent_folders() -> Option<PathBuf> {
     match lets_find_up::find_up(DATABASE_FILENAME) {
         Ok(res) => res,
@@ -19,7 +19,10 @@ fn main() -> std::process::ExitCode {
             ExitCode::SUCCESS
         }
         Err(err) => {
-            println!("ERROR: {err}");
+            eprintln!("ERROR: {err}");
+            if let Some(hint) = err.hint() {
+                eprintln!("HINT: {hint}");
+            }
 
             ExitCode::FAILURE
         }
@@ -0,0 +1,157 @@
+mod common;
+
+use crate::common::{create_random_file, file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, DbCommand, db::Setting, entrypoint};
+use std::path::{Path, PathBuf};
+
+const RULES: &str = r#"
+[[rules]]
+name = "pictures"
+tags = ["picture"]
+mime_type = "image/*"
+
+[[rules]]
+name = "png"
+tags = ["png"]
+extensions = ["PNG"]
+
+[[rules]]
+name = "temporary"
+tags = ["temporary", "generated"]
+name_regex = "^temp_tag_file_\\d$"
+max_size = 16
+
+[[rules]]
+name = "nested"
+tags = ["nested"]
+glob = "nested/**"
+min_size = 1
+"#;
+const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+fn autotag(db_path: &Path, paths: Vec<PathBuf>, dry_run: bool) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Autotag { paths, dry_run },
+    };
+    entrypoint(args)
+}
+
+fn prepare(root: &Path) -> PathBuf {
+    std::fs::write(root.join(".anytaggy-rules.toml"), RULES).unwrap();
+    std::fs::create_dir(root.join("nested")).unwrap();
+    let image_path = root.join("nested").join("image.txt");
+    std::fs::write(&image_path, PNG_HEADER).unwrap();
+    image_path
+}
+
+#[test]
+fn autotag_applies_rules() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, _, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let image_path = prepare(temp_dir.path());
+    let png_path = temp_dir.path().join("empty.png");
+    std::fs::write(&png_path, "").unwrap();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let out = autotag(&db_path, Vec::new(), false).unwrap();
+    assert_eq!(
+        Some(
+            "empty.png: png (png)\n\
+            nested/image.txt: picture (pictures)\n\
+            nested/image.txt: nested (nested)\n\
+            temp_tag_file_1: temporary (temporary)\n\
+            temp_tag_file_1: generated (temporary)\n\
+            temp_tag_file_2: temporary (temporary)\n\
+            temp_tag_file_2: generated (temporary)\n\
+            Added: empty.png\n\
+            Added: nested/image.txt\n\
+            Added: temp_tag_file_2\n\
+            Updated: temp_tag_file_1 (+temporary,generated)\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        Some("nested,picture".into()),
+        file_tags(&db_path, &image_path)
+    );
+    assert_eq!(
+        Some("generated,temporary".into()),
+        file_tags(&db_path, &tag_file_2)
+    );
+}
+
+#[test]
+fn autotag_dry_run() {
+    let (db_path, _, tag_file_2, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let image_path = prepare(temp_dir.path());
+    tag(&db_path, &image_path, &["image"]);
+
+    let out = autotag(&db_path, vec![temp_dir.path().join("nested")], true).unwrap();
+    assert_eq!(
+        Some(
+            "nested/image.txt: picture (pictures)\n\
+            nested/image.txt: nested (nested)\n\
+            Would update: nested/image.txt (+picture,nested)\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(Some("image".into()), file_tags(&db_path, &image_path));
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn autotag_on_tag() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let image_path = prepare(temp_dir.path());
+    let other_path = create_random_file(temp_dir.path(), "other");
+    tag(&db_path, &other_path, &["other"]);
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Db {
+            command: DbCommand::Set {
+                setting: Setting::Autotag,
+                enabled: true,
+            },
+        },
+    };
+    entrypoint(args).unwrap();
+
+    tag(&db_path, &image_path, &["image", "nested"]);
+    assert_eq!(
+        Some("image,nested,picture".into()),
+        file_tags(&db_path, &image_path)
+    );
+    assert_eq!(Some("other".into()), file_tags(&db_path, &other_path));
+}
+
+#[test]
+fn autotag_reports_invalid_rules() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let err = autotag(&db_path, Vec::new(), false).unwrap_err();
+    assert!(matches!(err, AppError::NoRules));
+
+    let rules_path = temp_dir.path().join(".anytaggy-rules.toml");
+    std::fs::write(
+        &rules_path,
+        "[[rules]]\nname = \"a\"\ntags = [\"a\"]\nname_regex = \"(\"",
+    )
+    .unwrap();
+    let err = autotag(&db_path, Vec::new(), false).unwrap_err();
+    assert!(matches!(err, AppError::InvalidRules(_)));
+
+    std::fs::write(
+        &rules_path,
+        "[[rules]]\nname = \"a\"\ntags = [\"a\"]\nsize = 1",
+    )
+    .unwrap();
+    let err = autotag(&db_path, Vec::new(), false).unwrap_err();
+    assert!(matches!(err, AppError::InvalidRules(_)));
+}
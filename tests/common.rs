@@ -2,12 +2,25 @@ use std::{
     fs::File,
     io::Write,
     path::{Path, PathBuf},
+    sync::Once,
 };
 
+use anytaggy::{Args, Command, DatabaseSources, config::CONFIG_ENV, entrypoint};
 use rand::Rng;
 use temp_dir::TempDir;
 
+/// Config file of the user running tests is never read, missing config is the same as empty one
+fn ignore_user_config() {
+    static IGNORE_USER_CONFIG: Once = Once::new();
+    IGNORE_USER_CONFIG.call_once(|| {
+        // SAFETY: value is set once and never changed, std serializes its own environment access
+        // and tests don't read environment in other ways
+        unsafe { std::env::set_var(CONFIG_ENV, "/nonexistent/anytaggy/config.toml") };
+    });
+}
+
 pub fn create_random_file(dir_path: &Path, name: &str) -> PathBuf {
+    ignore_user_config();
     let tag_file = dir_path.join(name);
     let mut file = File::create(&tag_file).unwrap();
     let mut rng = rand::rng();
@@ -18,6 +31,7 @@ pub fn create_random_file(dir_path: &Path, name: &str) -> PathBuf {
 #[allow(dead_code)]
 pub fn two_files_multiple_tags_prepare()
 -> (PathBuf, PathBuf, PathBuf, Vec<String>, Vec<String>, TempDir) {
+    ignore_user_config();
     let temp_dir = TempDir::new().unwrap();
     std::env::set_current_dir(temp_dir.path()).unwrap();
 
@@ -35,3 +49,28 @@ pub fn two_files_multiple_tags_prepare()
         temp_dir,
     )
 }
+
+#[allow(dead_code)]
+pub fn tag<T: ToString>(db_path: &Path, file_path: &Path, tags: &[T]) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Tag {
+            file_path: file_path.to_path_buf(),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            create: true,
+        },
+    };
+    entrypoint(args).unwrap();
+}
+
+#[allow(dead_code)]
+pub fn file_tags(db_path: &Path, file_path: &Path) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Tags {
+            file_path: Some(file_path.to_path_buf()),
+            sources: DatabaseSources::default(),
+        },
+    };
+    entrypoint(args).unwrap()
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use crate::common::{tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, CompletionShell, entrypoint};
+use std::path::Path;
+
+fn complete(db_path: &Path, words: &[&str]) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Complete {
+            index: words.len() - 1,
+            words: words.iter().map(|word| (*word).to_string()).collect(),
+        },
+    };
+    entrypoint(args)
+}
+
+#[test]
+fn completions_scripts() {
+    for (shell, dynamic) in [
+        (CompletionShell::Bash, "complete -F _anytaggy_dynamic"),
+        (CompletionShell::Zsh, "compdef _anytaggy_dynamic anytaggy"),
+        (
+            CompletionShell::Fish,
+            "complete -c anytaggy -f -n '__anytaggy_dynamic",
+        ),
+    ] {
+        let args = Args {
+            database_path: None,
+            command: Command::Completions { shell },
+        };
+        let script = entrypoint(args).unwrap().unwrap();
+        assert!(script.contains("rm-tags"));
+        assert!(script.contains("anytaggy __complete"));
+        assert!(script.contains(dynamic));
+    }
+}
+
+#[test]
+fn complete_files_tags() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    let out = complete(&db_path, &["anytaggy", "files", ""]).unwrap();
+    assert_eq!(Some("test\ntest2\ntest3\ntest4\ntest5".into()), out);
+
+    // Tags already in query are not suggested again
+    let out = complete(&db_path, &["anytaggy", "files", "test3", "test"]).unwrap();
+    assert_eq!(Some("test\ntest2\ntest4\ntest5".into()), out);
+
+    let out = complete(&db_path, &["anytaggy", "files", "test", "nothing"]).unwrap();
+    assert_eq!(None, out);
+
+    let out = complete(&db_path, &["anytaggy", "files", "--database", ""]);
+    assert!(matches!(out, Err(AppError::NoCompletions)));
+}
+
+#[test]
+fn complete_tag_option() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let file = tag_file_1.display().to_string();
+
+    let out = complete(&db_path, &["anytaggy", "tag", &file, "-t", "test"]).unwrap();
+    assert_eq!(Some("test\ntest2\ntest3\ntest4\ntest5".into()), out);
+
+    // Comma separated tags are completed after the last comma
+    let out = complete(&db_path, &["anytaggy", "tag", &file, "-t", "test4,test"]).unwrap();
+    assert_eq!(
+        Some("test4,test\ntest4,test2\ntest4,test3\ntest4,test5".into()),
+        out
+    );
+
+    let out = complete(
+        &db_path,
+        &["anytaggy", "tag", &file, "--tags=test5,test4,test3,test2,"],
+    )
+    .unwrap();
+    assert_eq!(Some("--tags=test5,test4,test3,test2,test".into()), out);
+
+    // Bash splits option and its value on '='
+    let out = complete(
+        &db_path,
+        &["anytaggy", "tag", &file, "--tags", "=", "test5"],
+    )
+    .unwrap();
+    assert_eq!(Some("test5".into()), out);
+
+    // File paths are completed by the shell
+    let out = complete(&db_path, &["anytaggy", "tag", ""]);
+    assert!(matches!(out, Err(AppError::NoCompletions)));
+}
+
+#[test]
+fn complete_namespaced_tags() {
+    let (db_path, tag_file_1, tag_file_2, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &["exif:camera=Canon", "test"]);
+    tag(&db_path, &tag_file_2, &["exif:camera=Nikon"]);
+    let file = tag_file_1.display().to_string();
+
+    // Bash splits words on ':' and '=', and replaces only text after the last of them
+    let out = complete(&db_path, &["anytaggy", "files", "exif", ":", "ca"]).unwrap();
+    assert_eq!(Some("camera=Canon\ncamera=Nikon".into()), out);
+
+    let out = complete(&db_path, &["anytaggy", "files", "exif", ":"]).unwrap();
+    assert_eq!(Some("camera=Canon\ncamera=Nikon".into()), out);
+
+    let out = complete(
+        &db_path,
+        &["anytaggy", "files", "exif", ":", "camera", "=", "N"],
+    )
+    .unwrap();
+    assert_eq!(Some("Nikon".into()), out);
+
+    let out = complete(
+        &db_path,
+        &[
+            "anytaggy",
+            "tag",
+            &file,
+            "-t",
+            "test,exif",
+            ":",
+            "camera",
+            "=",
+        ],
+    )
+    .unwrap();
+    assert_eq!(Some("Canon\nNikon".into()), out);
+
+    // Other shells pass whole words
+    let out = complete(&db_path, &["anytaggy", "files", "exif:camera=N"]).unwrap();
+    assert_eq!(Some("exif:camera=Nikon".into()), out);
+}
+
+#[test]
+fn complete_untag_file_tags() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let file = tag_file_2.display().to_string();
+
+    let out = complete(&db_path, &["anytaggy", "untag", &file, "-t", ""]).unwrap();
+    assert_eq!(Some("test3\ntest4\ntest5".into()), out);
+
+    let out = complete(&db_path, &["anytaggy", "untag", "-t", "test3,", &file]);
+    assert!(matches!(out, Err(AppError::NoCompletions)));
+
+    let out = complete(&db_path, &["anytaggy", "untag", "-t", "test3,"]).unwrap();
+    assert_eq!(None, out);
+}
+
+#[test]
+fn complete_database_from_words() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let args = Args {
+        database_path: None,
+        command: Command::Complete {
+            index: 4,
+            words: [
+                "anytaggy",
+                "-d",
+                db_path.to_str().unwrap(),
+                "files",
+                "test2",
+            ]
+            .iter()
+            .map(|word| (*word).to_string())
+            .collect(),
+        },
+    };
+    assert_eq!(Some("test2".into()), entrypoint(args).unwrap());
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use crate::common::two_files_multiple_tags_prepare;
+use anytaggy::db::{Database, DatabaseMode, File};
+use std::thread;
+
+const WRITERS: usize = 16;
+
+fn fake_file(index: usize) -> File {
+    File {
+        path: format!("file_{index}"),
+        name: format!("file_{index}"),
+        contents_hash: format!("contents_{index}"),
+        fingerprint_hash: format!("fingerprint_{index}"),
+        mime_type: None,
+    }
+}
+
+#[test]
+fn concurrent_writers() {
+    let (db_path, _, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|index| {
+            let db_path = db_path.clone();
+            thread::spawn(move || {
+                let mut db = Database::new(&DatabaseMode::ReadWriteCreate, &db_path).unwrap();
+                db.tag_file(&fake_file(index), &["shared", &format!("own_{index}")])
+                    .unwrap();
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let db = Database::new(&DatabaseMode::Read, &db_path).unwrap();
+    assert_eq!(WRITERS, db.get_files_by_tags(&["shared"]).unwrap().len());
+    assert_eq!(WRITERS + 1, db.get_all_tags().unwrap().len());
+}
+
+#[test]
+fn concurrent_readers_and_writers() {
+    let (db_path, _, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+    Database::new(&DatabaseMode::ReadWriteCreate, &db_path).unwrap();
+
+    let workers: Vec<_> = (0..WRITERS)
+        .map(|index| {
+            let db_path = db_path.clone();
+            thread::spawn(move || {
+                if index % 2 == 0 {
+                    let mut db = Database::new(&DatabaseMode::ReadWrite, &db_path).unwrap();
+                    db.tag_file(&fake_file(index), &["shared"]).unwrap();
+                } else {
+                    let db = Database::new(&DatabaseMode::Read, &db_path).unwrap();
+                    db.get_files_by_tags(&["shared"]).unwrap();
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let db = Database::new(&DatabaseMode::Read, &db_path).unwrap();
+    assert_eq!(WRITERS / 2, db.get_files().unwrap().len());
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use crate::common::create_random_file;
+use anytaggy::{AppError, config::Config};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use temp_dir::TempDir;
+
+const CONFIG: &str = r#"
+default = "/data/.anytaggy.db"
+
+[databases]
+photos = "/mnt/photos/.anytaggy.db"
+music = "/mnt/music/.anytaggy.db"
+
+[[directories]]
+path = "/mnt"
+database = "@music"
+
+[[directories]]
+path = "/mnt/photos/raw"
+database = "@photos"
+
+[[directories]]
+path = "/home/user/projects"
+database = "/home/user/.anytaggy.db"
+"#;
+
+#[test]
+fn resolve_named_database() {
+    let config = Config::parse(CONFIG).unwrap();
+
+    assert_eq!(
+        PathBuf::from("/mnt/photos/.anytaggy.db"),
+        config.resolve_database(Path::new("@photos")).unwrap()
+    );
+    assert_eq!(
+        PathBuf::from("some/.anytaggy.db"),
+        config
+            .resolve_database(Path::new("some/.anytaggy.db"))
+            .unwrap()
+    );
+    assert!(matches!(
+        config.resolve_database(Path::new("@missing")),
+        Err(AppError::UnknownNamedDatabase(name)) if name == "missing"
+    ));
+}
+
+#[test]
+fn resolve_directory_database() {
+    let config = Config::parse(CONFIG).unwrap();
+
+    // Deepest configured directory wins
+    assert_eq!(
+        Some(PathBuf::from("/mnt/photos/.anytaggy.db")),
+        config
+            .directory_database(Path::new("/mnt/photos/raw/2024"))
+            .unwrap()
+    );
+    assert_eq!(
+        Some(PathBuf::from("/mnt/music/.anytaggy.db")),
+        config.directory_database(Path::new("/mnt/photos")).unwrap()
+    );
+    assert_eq!(
+        Some(PathBuf::from("/home/user/.anytaggy.db")),
+        config
+            .directory_database(Path::new("/home/user/projects"))
+            .unwrap()
+    );
+    assert_eq!(
+        None,
+        config.directory_database(Path::new("/home/user")).unwrap()
+    );
+    assert_eq!(
+        Some(PathBuf::from("/data/.anytaggy.db")),
+        config.default_database().unwrap()
+    );
+}
+
+#[test]
+fn empty_config() {
+    let config = Config::parse("").unwrap();
+
+    assert_eq!(None, config.default_database().unwrap());
+    assert_eq!(None, config.directory_database(Path::new("/")).unwrap());
+}
+
+#[test]
+fn invalid_config() {
+    assert!(matches!(
+        Config::parse("unknown_option = true"),
+        Err(AppError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        Config::parse("[databases]\nphotos = 1"),
+        Err(AppError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn database_from_environment() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("tmp_db.db");
+    let config_path = temp_dir.path().join("config.toml");
+    let tag_file = create_random_file(temp_dir.path(), "temp_tag_file");
+    std::fs::write(
+        &config_path,
+        format!("[databases]\nnamed = \"{}\"", db_path.display()),
+    )
+    .unwrap();
+
+    let anytaggy = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_anytaggy"));
+        command
+            .current_dir(temp_dir.path())
+            .env("ANYTAGGY_CONFIG", &config_path);
+        command
+    };
+
+    let status = anytaggy()
+        .env("ANYTAGGY_DB", &db_path)
+        .args(["init"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = anytaggy()
+        .env("ANYTAGGY_DB", &db_path)
+        .args(["tag", "-t", "test"])
+        .arg(&tag_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let out = anytaggy()
+        .env("ANYTAGGY_DB", "@named")
+        .args(["tags"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert_eq!("test\n", String::from_utf8_lossy(&out.stdout));
+
+    let out = anytaggy()
+        .env("ANYTAGGY_DB", "@missing")
+        .args(["tags"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+}
+
+#[test]
+fn invalid_config_read_only_when_needed() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("tmp_db.db");
+    let config_path = temp_dir.path().join("config.toml");
+    let tag_file = create_random_file(temp_dir.path(), "temp_tag_file");
+    std::fs::write(&config_path, "unknown_option = true").unwrap();
+
+    let anytaggy = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_anytaggy"));
+        command
+            .current_dir(temp_dir.path())
+            .env("ANYTAGGY_CONFIG", &config_path)
+            .env_remove("ANYTAGGY_DB");
+        command
+    };
+
+    let status = anytaggy()
+        .arg("-d")
+        .arg(&db_path)
+        .args(["tag", "--create", "-t", "test"])
+        .arg(&tag_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let out = anytaggy()
+        .arg("-d")
+        .arg(&db_path)
+        .arg("tags")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert_eq!("test\n", String::from_utf8_lossy(&out.stdout));
+
+    let out = anytaggy().args(["-d", "@named", "tags"]).output().unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Invalid config file"));
+
+    let out = anytaggy().arg("tags").output().unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Invalid config file"));
+}
@@ -0,0 +1,302 @@
+mod common;
+
+use crate::common::{create_random_file, two_files_multiple_tags_prepare};
+use anytaggy::{
+    AppError, Args, Command, DatabaseSources, DbCommand,
+    db::{Database, DatabaseError, DatabaseMode, SCHEMA_VERSION},
+    entrypoint,
+};
+use std::time::Duration;
+
+#[test]
+fn read_outdated_database() {
+    let (db_path, _, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    // Database from some older version, without any migrations applied
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    connection
+        .execute("CREATE TABLE legacy (id INTEGER)", [])
+        .unwrap();
+    drop(connection);
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::NeedsUpgrade { found: 0 }))
+    ));
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Db {
+            command: DbCommand::Upgrade,
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(
+        Some(format!("Database schema is at version {SCHEMA_VERSION}")),
+        out
+    );
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+}
+
+#[test]
+fn write_outdated_database() {
+    let (db_path, tag_file, _, test_tags, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    connection
+        .execute("CREATE TABLE legacy (id INTEGER)", [])
+        .unwrap();
+    drop(connection);
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::RmTags {
+            tags: test_tags.clone(),
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::NeedsUpgrade { found: 0 }))
+    ));
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file,
+            tags: test_tags,
+            create: true,
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::NeedsUpgrade { found: 0 }))
+    ));
+}
+
+#[test]
+fn newer_database() {
+    let (db_path, tag_file, _, test_tags, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file,
+            tags: test_tags,
+            create: true,
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    connection
+        .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+        .unwrap();
+    drop(connection);
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::NewerThanBinary { .. }))
+    ));
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Db {
+            command: DbCommand::Upgrade,
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::NewerThanBinary { .. }))
+    ));
+}
+
+#[test]
+fn upgrade_missing_database() {
+    let (db_path, _, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let args = Args {
+        database_path: Some(db_path),
+        command: Command::Db {
+            command: DbCommand::Upgrade,
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(out, Err(AppError::DatabaseNotFound)));
+}
+
+#[test]
+fn corrupt_database() {
+    let (db_path, tag_file, _, test_tags, _, _temp_dir) = two_files_multiple_tags_prepare();
+    std::fs::write(
+        &db_path,
+        "definitely not a sqlite database, but long enough to be read",
+    )
+    .unwrap();
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::Corrupt))
+    ));
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file,
+            tags: test_tags,
+            create: true,
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(
+        out,
+        Err(AppError::Database(DatabaseError::Corrupt))
+    ));
+}
+
+#[test]
+fn locked_database() {
+    let (db_path, tag_file, _, test_tags, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file.clone(),
+            tags: test_tags.clone(),
+            create: true,
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    connection.execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+    let mut db = Database::new_with_busy_timeout(
+        &DatabaseMode::ReadWrite,
+        &db_path,
+        Duration::from_millis(10),
+    )
+    .unwrap();
+    let out = db.delete_tags(&["test"]);
+    assert!(matches!(out, Err(DatabaseError::Locked)));
+
+    connection.execute_batch("ROLLBACK").unwrap();
+    db.delete_tags(&["test"]).unwrap();
+}
+
+#[test]
+fn upgrade_legacy_paths() {
+    let (db_path, tag_file_1, tag_file_2, test_tags, _, temp_dir) =
+        two_files_multiple_tags_prepare();
+    std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+    let sub_file = create_random_file(&temp_dir.path().join("sub"), "photo");
+    let root_file = create_random_file(temp_dir.path(), "photo");
+
+    for tag_file in [&tag_file_1, &tag_file_2, &sub_file] {
+        let args = Args {
+            database_path: Some(db_path.clone()),
+            command: Command::Tag {
+                file_path: tag_file.clone(),
+                tags: test_tags.clone(),
+                create: true,
+            },
+        };
+        entrypoint(args).unwrap();
+    }
+
+    // Older versions stored paths as given on command line, 'sub/photo' was tagged from 'sub'
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    for (path, name) in [
+        (
+            tag_file_1.canonicalize().unwrap().display().to_string(),
+            "temp_tag_file_1",
+        ),
+        ("./temp_tag_file_2".into(), "temp_tag_file_2"),
+        ("./photo".into(), "photo"),
+    ] {
+        connection
+            .execute(
+                "UPDATE files SET path = ?1, fingerprint_hash = 'legacy_' || ?2 WHERE name = ?2",
+                (path, name),
+            )
+            .unwrap();
+    }
+    drop(connection);
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Db {
+            command: DbCommand::Upgrade,
+        },
+    };
+    entrypoint(args).unwrap();
+
+    for tag_file in [&tag_file_1, &tag_file_2] {
+        let args = Args {
+            database_path: Some(db_path.clone()),
+            command: Command::Tags {
+                file_path: Some(tag_file.clone()),
+                sources: DatabaseSources::default(),
+            },
+        };
+        let out = entrypoint(args).unwrap();
+        assert_eq!(Some(test_tags.join(",")), out);
+    }
+
+    // 'photo' in root has other contents, so stored path is not moved onto it
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: Some(root_file),
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+    let db = Database::new(&DatabaseMode::Read, &db_path).unwrap();
+    let mut paths: Vec<String> = db
+        .get_stored_files()
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect();
+    paths.sort();
+    assert_eq!(vec!["./photo", "temp_tag_file_1", "temp_tag_file_2"], paths);
+}
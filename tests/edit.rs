@@ -0,0 +1,185 @@
+mod common;
+
+use crate::common::{file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, entrypoint};
+use std::path::Path;
+
+fn edit(db_path: &Path, targets: &[String], editor: &str) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Edit {
+            targets: targets.to_vec(),
+            editor: Some(editor.into()),
+        },
+    };
+    entrypoint(args)
+}
+
+fn file_name(file_path: &Path) -> String {
+    file_path.file_name().unwrap().display().to_string()
+}
+
+#[test]
+fn edit_adds_and_removes_tags() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    let out = edit(
+        &db_path,
+        &[],
+        &format!(
+            "sed -i -e 's/test2,//' -e '/^{}/s/$/,new/'",
+            file_name(&tag_file_2)
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        Some(format!(
+            "Updated: {} (+,-test2)\nUpdated: {} (+new,-)\nUnchanged: 0 files",
+            file_name(&tag_file_1),
+            file_name(&tag_file_2)
+        )),
+        out
+    );
+    assert_eq!(Some("test,test3".into()), file_tags(&db_path, &tag_file_1));
+    assert_eq!(
+        Some("test3,test4,test5,new".into()),
+        file_tags(&db_path, &tag_file_2)
+    );
+}
+
+#[test]
+fn edit_query_lists_matching_files() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    // Every listed line gets a new tag, so only files matching the query change
+    let out = edit(&db_path, &["test4".into()], "sed -i 's/$/,new/'").unwrap();
+    assert_eq!(
+        Some(format!(
+            "Updated: {} (+new,-)\nUnchanged: 0 files",
+            file_name(&tag_file_2)
+        )),
+        out
+    );
+    assert_eq!(
+        Some(test_tags_1.join(",")),
+        file_tags(&db_path, &tag_file_1)
+    );
+}
+
+#[test]
+fn edit_untagged_file_by_path() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, _, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let out = edit(
+        &db_path,
+        &[tag_file_2.display().to_string()],
+        "sed -i 's/$/new,other/'",
+    )
+    .unwrap();
+    assert_eq!(
+        Some(format!(
+            "Updated: {} (+new,other,-)\nUnchanged: 0 files",
+            file_name(&tag_file_2)
+        )),
+        out
+    );
+    assert_eq!(Some("new,other".into()), file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn edit_untracked_copy_leaves_original() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    let copy = temp_dir.path().join("copy_of_tag_file_1");
+    std::fs::copy(&tag_file_1, &copy).unwrap();
+
+    // Copy is matched by its own fingerprint like in 'tag', so it is not the original
+    let out = edit(&db_path, &[copy.display().to_string()], "sed -i 's/$/new/'");
+    assert!(matches!(out, Err(AppError::Database(_))));
+    assert_eq!(None, file_tags(&db_path, &copy));
+    assert_eq!(
+        Some("test,test2,test3".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+}
+
+#[test]
+fn edit_removed_lines_are_unchanged() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    let out = edit(&db_path, &[], "sed -i '1d'").unwrap();
+    assert_eq!(Some("Unchanged: 1 files".into()), out);
+    assert_eq!(
+        Some(test_tags_1.join(",")),
+        file_tags(&db_path, &tag_file_1)
+    );
+}
+
+#[test]
+fn edit_invalid_line_changes_nothing() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    // First line is valid, second one is not
+    let out = edit(
+        &db_path,
+        &[],
+        "sed -i -e '1s/test2,//' -e '2s/.*/no tags here/'",
+    );
+    assert!(matches!(out, Err(AppError::InvalidEdit(2, _))));
+    assert_eq!(
+        Some(test_tags_1.join(",")),
+        file_tags(&db_path, &tag_file_1)
+    );
+
+    let out = edit(&db_path, &[], "sed -i 's/^[^\t]*/unknown/'");
+    assert!(matches!(out, Err(AppError::InvalidEdit(1, _))));
+
+    let out = edit(&db_path, &[], "sed -i '1p'");
+    assert!(matches!(out, Err(AppError::InvalidEdit(2, _))));
+}
+
+#[test]
+fn edit_editor_failure() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let out = edit(&db_path, &[], "false");
+    assert!(matches!(out, Err(AppError::EditorFailed(_))));
+    assert_eq!(
+        Some(test_tags_1.join(",")),
+        file_tags(&db_path, &tag_file_1)
+    );
+
+    let out = edit(&db_path, &[], "true").unwrap();
+    assert_eq!(Some("Unchanged: 1 files".into()), out);
+}
+
+#[test]
+fn edit_file_is_private() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    let mode_path = temp_dir.path().join("mode");
+
+    let out = edit(
+        &db_path,
+        &[],
+        &format!("stat -c %a \"$1\" > '{}'; true", mode_path.display()),
+    )
+    .unwrap();
+    assert_eq!(Some("Unchanged: 1 files".into()), out);
+    assert_eq!("600\n", std::fs::read_to_string(&mode_path).unwrap());
+}
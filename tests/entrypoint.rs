@@ -1,7 +1,7 @@
 mod common;
 
 use crate::common::create_random_file;
-use anytaggy::{AppError, Args, Command, DATABASE_FILENAME, entrypoint};
+use anytaggy::{AppError, Args, Command, DATABASE_FILENAME, DatabaseSources, entrypoint};
 use std::{fs::create_dir, path::PathBuf};
 use temp_dir::TempDir;
 
@@ -22,6 +22,7 @@ fn create_and_find_database_in_parent() {
         command: Command::Tag {
             file_path: tag_file,
             tags: test_tags,
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -30,7 +31,10 @@ fn create_and_find_database_in_parent() {
     std::env::set_current_dir(subfolder).unwrap();
     let args = Args {
         database_path: None,
-        command: Command::Tags { file_path: None },
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args).unwrap();
     assert_eq!(Some("test".into()), out);
@@ -50,6 +54,7 @@ fn create_and_find_database_in_current_dir() {
         command: Command::Tag {
             file_path: tag_file,
             tags: test_tags,
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -57,7 +62,10 @@ fn create_and_find_database_in_current_dir() {
 
     let args = Args {
         database_path: None,
-        command: Command::Tags { file_path: None },
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args).unwrap();
     assert_eq!(Some("test".into()), out);
@@ -70,7 +78,10 @@ fn dont_find_database() {
 
     let args = Args {
         database_path: Some(PathBuf::default()),
-        command: Command::Tags { file_path: None },
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args);
     assert!(matches!(out, Err(AppError::DatabaseNotFound)));
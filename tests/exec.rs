@@ -0,0 +1,133 @@
+mod common;
+
+use crate::common::{create_random_file, tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, DatabaseSources, entrypoint};
+use std::path::Path;
+
+fn exec(
+    db_path: &Path,
+    tags: &[&str],
+    command: &[&str],
+    batch: bool,
+) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Exec {
+            tags: tags.iter().map(|tag| (*tag).to_string()).collect(),
+            batch,
+            command: command.iter().map(|arg| (*arg).to_string()).collect(),
+            sources: DatabaseSources::default(),
+        },
+    };
+    entrypoint(args)
+}
+
+#[test]
+fn exec_each_file() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let spaced_file = create_random_file(temp_dir.path(), "file with spaces");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    tag(&db_path, &spaced_file, &["test3"]);
+
+    let out = exec(&db_path, &["test3"], &["cp", "{}", "{}.copy"], false).unwrap();
+    assert_eq!(None, out);
+    for file in [&tag_file_1, &tag_file_2, &spaced_file] {
+        assert_eq!(
+            std::fs::read(file).unwrap(),
+            std::fs::read(format!("{}.copy", file.display())).unwrap()
+        );
+    }
+
+    // Only files with all of the tags
+    let out = exec(&db_path, &["test3", "test4"], &["rm"], false).unwrap();
+    assert_eq!(None, out);
+    assert!(tag_file_1.exists());
+    assert!(!tag_file_2.exists());
+}
+
+#[test]
+fn exec_batch() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let backup = temp_dir.path().join("backup");
+    std::fs::create_dir(&backup).unwrap();
+
+    let out = exec(
+        &db_path,
+        &["test3"],
+        &["cp", "{}", backup.to_str().unwrap()],
+        true,
+    )
+    .unwrap();
+    assert_eq!(None, out);
+    assert!(backup.join("temp_tag_file_1").exists());
+    assert!(backup.join("temp_tag_file_2").exists());
+
+    // Single invocation, so the second file is not there to be moved again
+    let out = exec(
+        &db_path,
+        &["test3"],
+        &["mv", "-t", backup.to_str().unwrap()],
+        true,
+    )
+    .unwrap();
+    assert_eq!(None, out);
+    assert!(!tag_file_1.exists());
+    assert!(!tag_file_2.exists());
+}
+
+#[test]
+fn exec_batch_chunks() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    // 70 paths of 2000 bytes don't fit in arguments of a single invocation
+    let mut dir_path = temp_dir.path().to_path_buf();
+    for name in ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"] {
+        dir_path.push(name.repeat(200));
+    }
+    std::fs::create_dir_all(&dir_path).unwrap();
+    for index in 0..70 {
+        let file = create_random_file(&dir_path, &format!("file_{index}"));
+        tag(&db_path, &file, &["long"]);
+    }
+
+    let counts = temp_dir.path().join("counts");
+    let script = format!("echo $# >> '{}'", counts.display());
+    let out = exec(&db_path, &["long"], &["sh", "-c", &script, "sh"], true).unwrap();
+    assert_eq!(None, out);
+    let counts: Vec<usize> = std::fs::read_to_string(&counts)
+        .unwrap()
+        .lines()
+        .map(|count| count.parse().unwrap())
+        .collect();
+    assert_eq!(2, counts.len());
+    assert_eq!(70, counts.iter().sum::<usize>());
+
+    let out = exec(&db_path, &["long"], &["false"], true);
+    assert!(matches!(out, Err(AppError::ExecFailed(2, 2))));
+}
+
+#[test]
+fn exec_failures() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    let condition = format!("test \"$1\" != '{}'", tag_file_1.display());
+    let out = exec(&db_path, &["test3"], &["sh", "-c", &condition, "sh"], false);
+    assert!(matches!(out, Err(AppError::ExecFailed(1, 2))));
+
+    let out = exec(&db_path, &["test3"], &["false"], true);
+    assert!(matches!(out, Err(AppError::ExecFailed(1, 1))));
+
+    // Nothing to run command for
+    let out = exec(&db_path, &["test", "test5"], &["false"], false).unwrap();
+    assert_eq!(None, out);
+
+    let out = exec(&db_path, &["test3"], &[], false);
+    assert!(matches!(out, Err(AppError::NoCommandSpecified)));
+}
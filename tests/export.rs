@@ -0,0 +1,69 @@
+mod common;
+
+use crate::common::two_files_multiple_tags_prepare;
+use anytaggy::{Args, Command, ExportFormat, entrypoint};
+
+#[test]
+fn export_json() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+
+    for (file_path, tags) in [(&tag_file_1, &test_tags_1), (&tag_file_2, &test_tags_2)] {
+        let args = Args {
+            database_path: Some(db_path.clone()),
+            command: Command::Tag {
+                file_path: file_path.clone(),
+                tags: tags.clone(),
+                create: true,
+            },
+        };
+        entrypoint(args).unwrap();
+    }
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Export {
+            format: ExportFormat::Json,
+        },
+    };
+    let out = entrypoint(args).unwrap().unwrap();
+    let document: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+    assert_eq!(1, document["version"]);
+    assert_eq!(
+        serde_json::json!(["test", "test2", "test3", "test4", "test5"]),
+        document["tags"]
+    );
+    let files = document["files"].as_array().unwrap();
+    assert_eq!(2, files.len());
+    assert_eq!("temp_tag_file_1", files[0]["path"]);
+    assert_eq!("temp_tag_file_1", files[0]["name"]);
+    assert_eq!(serde_json::json!(test_tags_1), files[0]["tags"]);
+    assert_eq!(64, files[0]["contents_hash"].as_str().unwrap().len());
+    assert_eq!("temp_tag_file_2", files[1]["path"]);
+    assert_eq!(serde_json::json!(test_tags_2), files[1]["tags"]);
+}
+
+#[test]
+fn export_empty_database() {
+    let (db_path, _, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let args = Args {
+        database_path: None,
+        command: Command::Init {
+            directory: Some(db_path.parent().unwrap().to_path_buf()),
+        },
+    };
+    entrypoint(args).unwrap();
+
+    let args = Args {
+        database_path: Some(db_path.with_file_name(anytaggy::DATABASE_FILENAME)),
+        command: Command::Export {
+            format: ExportFormat::Json,
+        },
+    };
+    let out = entrypoint(args).unwrap().unwrap();
+    let document: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(serde_json::json!([]), document["files"]);
+    assert_eq!(serde_json::json!([]), document["tags"]);
+}
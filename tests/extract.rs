@@ -0,0 +1,167 @@
+mod common;
+
+use crate::common::{file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{Args, Command, entrypoint};
+use exif::{Field, In, Tag, Value, experimental::Writer};
+use id3::TagLike;
+use std::path::{Path, PathBuf};
+
+fn ascii(tag: Tag, value: &str) -> Field {
+    Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+    }
+}
+
+/// JPEG with nothing but EXIF segment
+fn write_jpeg(file_path: &Path, make: &str, with_gps: bool) {
+    let fields = [
+        ascii(Tag::Make, make),
+        ascii(Tag::Model, &format!("{make} EOS 5D")),
+        ascii(Tag::LensModel, "EF 50mm f/1.8"),
+        ascii(Tag::DateTimeOriginal, "2017:05:01 12:30:00"),
+    ];
+    let latitude = Field {
+        tag: Tag::GPSLatitude,
+        ifd_num: In::PRIMARY,
+        value: Value::Rational(vec![(50, 1).into(), (27, 1).into(), (0, 1).into()]),
+    };
+    let mut writer = Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    if with_gps {
+        writer.push_field(&latitude);
+    }
+    let mut tiff = std::io::Cursor::new(Vec::new());
+    writer.write(&mut tiff, true).unwrap();
+    let tiff = tiff.into_inner();
+
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    jpeg.extend_from_slice(&u16::try_from(tiff.len() + 8).unwrap().to_be_bytes());
+    jpeg.extend_from_slice(b"Exif\0\0");
+    jpeg.extend_from_slice(&tiff);
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    std::fs::write(file_path, jpeg).unwrap();
+}
+
+fn extract(db_path: &Path, paths: Vec<PathBuf>) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Extract { paths },
+    };
+    entrypoint(args).unwrap()
+}
+
+#[test]
+fn extract_exif() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let photo_path = temp_dir.path().join("photo.jpg");
+    write_jpeg(&photo_path, "Canon", true);
+    tag(&db_path, &photo_path, &["holiday"]);
+
+    let out = extract(&db_path, vec![photo_path.clone()]);
+    assert_eq!(
+        Some(
+            "Extracted: photo.jpg (+exif:camera=Canon EOS 5D,+exif:lens=EF 50mm f/1.8,+exif:date_taken=2017-05-01,+exif:gps)\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        Some(
+            "holiday,exif:camera=Canon EOS 5D,exif:lens=EF 50mm f/1.8,exif:date_taken=2017-05-01,exif:gps"
+                .into()
+        ),
+        file_tags(&db_path, &photo_path)
+    );
+
+    let out = extract(&db_path, vec![photo_path]);
+    assert_eq!(Some("Unchanged: 1 files".into()), out);
+}
+
+#[test]
+fn extract_refreshes_changed_files() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let photo_path = temp_dir.path().join("photo.jpg");
+    write_jpeg(&photo_path, "Canon", true);
+    tag(&db_path, &photo_path, &["holiday"]);
+    extract(&db_path, vec![photo_path.clone()]);
+
+    write_jpeg(&photo_path, "Nikon", false);
+    let out = extract(&db_path, vec![photo_path.clone()]);
+    assert_eq!(
+        Some(
+            "Extracted: photo.jpg (+exif:camera=Nikon EOS 5D,-exif:camera=Canon EOS 5D,-exif:gps)\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        Some(
+            "holiday,exif:lens=EF 50mm f/1.8,exif:date_taken=2017-05-01,exif:camera=Nikon EOS 5D"
+                .into()
+        ),
+        file_tags(&db_path, &photo_path)
+    );
+}
+
+#[test]
+fn extract_keeps_tags_of_copied_file() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let photo_path = temp_dir.path().join("photo.jpg");
+    write_jpeg(&photo_path, "Canon", false);
+    tag(&db_path, &photo_path, &["holiday"]);
+    let copy_path = temp_dir.path().join("copy.jpg");
+    std::fs::copy(&photo_path, &copy_path).unwrap();
+
+    // Copy has the same contents, but it's a different file
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Extract {
+            paths: vec![copy_path.clone()],
+        },
+    };
+    let _ = entrypoint(args);
+    assert_eq!(Some("holiday".into()), file_tags(&db_path, &photo_path));
+    assert_eq!(None, file_tags(&db_path, &copy_path));
+}
+
+#[test]
+fn extract_audio_tags() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, _, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    let song_path = temp_dir.path().join("song.mp3");
+    std::fs::write(&song_path, "").unwrap();
+    let mut id3_tag = id3::Tag::new();
+    id3_tag.set_artist("Artist");
+    id3_tag.set_album("Album");
+    id3_tag.set_year(1999);
+    id3_tag
+        .write_to_path(&song_path, id3::Version::Id3v24)
+        .unwrap();
+    tag(&db_path, &song_path, &["music"]);
+    let other_song_path = temp_dir.path().join("other_song.mp3");
+    std::fs::copy(&song_path, &other_song_path).unwrap();
+
+    let out = extract(&db_path, Vec::new());
+    assert_eq!(
+        Some(
+            "Extracted: song.mp3 (+audio:artist=Artist,+audio:album=Album,+audio:year=1999)\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        Some("music,audio:artist=Artist,audio:album=Album,audio:year=1999".into()),
+        file_tags(&db_path, &song_path)
+    );
+    // Files not in database are not added
+    assert_eq!(None, file_tags(&db_path, &other_song_path));
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
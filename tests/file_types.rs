@@ -0,0 +1,124 @@
+mod common;
+
+use crate::common::{tag, two_files_multiple_tags_prepare};
+use anytaggy::{
+    AppError, Args, Command, DatabaseSources, DbCommand, db::DatabaseError, entrypoint,
+};
+use std::path::{Path, PathBuf};
+
+const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+const JPEG_HEADER: &[u8] = b"\xFF\xD8\xFF\xE0\0\x10JFIF\0";
+
+fn files(db_path: &Path, tags: &[&str]) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Files {
+            tags: Some(tags.iter().map(ToString::to_string).collect()),
+            sources: DatabaseSources::default(),
+        },
+    };
+    entrypoint(args).unwrap()
+}
+
+/// Extensions don't match contents, so only magic bytes could tell the type
+fn prepare(root: &Path) -> (PathBuf, PathBuf) {
+    let png_path = root.join("image.txt");
+    std::fs::write(&png_path, PNG_HEADER).unwrap();
+    let jpeg_path = root.join("photo.png");
+    std::fs::write(&jpeg_path, JPEG_HEADER).unwrap();
+    (png_path, jpeg_path)
+}
+
+#[test]
+fn files_by_type() {
+    let (db_path, tag_file_1, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let (png_path, jpeg_path) = prepare(temp_dir.path());
+    let text_path = temp_dir.path().join("text.jpg");
+    std::fs::write(&text_path, "plain text").unwrap();
+    tag(&db_path, &png_path, &["picture"]);
+    tag(&db_path, &jpeg_path, &["picture", "holiday"]);
+    tag(&db_path, &text_path, &["picture"]);
+    tag(&db_path, &tag_file_1, &["holiday"]);
+
+    assert_eq!(
+        Some(format!("{}\n{}", png_path.display(), jpeg_path.display())),
+        files(&db_path, &["type:image"])
+    );
+    assert_eq!(
+        Some(jpeg_path.display().to_string()),
+        files(&db_path, &["type:image/jpeg"])
+    );
+    assert_eq!(
+        Some(jpeg_path.display().to_string()),
+        files(&db_path, &["holiday", "type:image"])
+    );
+    assert_eq!(None, files(&db_path, &["type:image/jp"]));
+    assert_eq!(None, files(&db_path, &["type:video"]));
+}
+
+#[test]
+fn type_tags_are_reserved() {
+    let (db_path, tag_file_1, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Tag {
+            file_path: tag_file_1,
+            tags: vec!["type:image".into()],
+            create: true,
+        },
+    };
+    let err = entrypoint(args).unwrap_err();
+    assert!(matches!(
+        err,
+        AppError::Database(DatabaseError::ReservedTagName(name)) if name == "type:image"
+    ));
+}
+
+#[test]
+fn type_is_filled_for_existing_files() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let (png_path, _) = prepare(temp_dir.path());
+    tag(&db_path, &png_path, &["picture"]);
+
+    // Files stored before types were detected
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    connection
+        .execute("UPDATE files SET mime_type = NULL", [])
+        .unwrap();
+    drop(connection);
+    assert_eq!(None, files(&db_path, &["type:image"]));
+
+    tag(&db_path, &png_path, &["png"]);
+    assert_eq!(
+        Some(png_path.display().to_string()),
+        files(&db_path, &["type:image/png"])
+    );
+}
+
+#[test]
+fn upgrade_detects_types() {
+    let (db_path, _, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let (png_path, jpeg_path) = prepare(temp_dir.path());
+    tag(&db_path, &png_path, &["picture"]);
+    tag(&db_path, &jpeg_path, &["picture"]);
+
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    connection
+        .execute("UPDATE files SET mime_type = NULL", [])
+        .unwrap();
+    drop(connection);
+    std::fs::remove_file(&jpeg_path).unwrap();
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Db {
+            command: DbCommand::Upgrade,
+        },
+    };
+    entrypoint(args).unwrap();
+    assert_eq!(
+        Some(png_path.display().to_string()),
+        files(&db_path, &["type:image"])
+    );
+}
@@ -1,7 +1,7 @@
 mod common;
 
 use crate::common::two_files_multiple_tags_prepare;
-use anytaggy::{AppError, Args, Command, entrypoint};
+use anytaggy::{AppError, Args, Command, DatabaseSources, entrypoint};
 
 #[test]
 fn no_files_database() {
@@ -9,7 +9,10 @@ fn no_files_database() {
 
     let args = Args {
         database_path: None,
-        command: Command::Files { tags: None },
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args);
     assert!(matches!(out, Err(AppError::DatabaseNotFound)));
@@ -26,6 +29,7 @@ fn files_joined_tag() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -36,6 +40,7 @@ fn files_joined_tag() {
         command: Command::Tag {
             file_path: tag_file_2.clone(),
             tags: test_tags_2.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -45,6 +50,7 @@ fn files_joined_tag() {
         database_path: Some(db_path.clone()),
         command: Command::Files {
             tags: Some(vec!["test3".into()]),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -69,6 +75,7 @@ fn files_left_tag() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -79,6 +86,7 @@ fn files_left_tag() {
         command: Command::Tag {
             file_path: tag_file_2.clone(),
             tags: test_tags_2.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -88,6 +96,7 @@ fn files_left_tag() {
         database_path: Some(db_path.clone()),
         command: Command::Files {
             tags: Some(vec!["test".into(), "test2".into()]),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -105,6 +114,7 @@ fn files_right_tag() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -115,6 +125,7 @@ fn files_right_tag() {
         command: Command::Tag {
             file_path: tag_file_2.clone(),
             tags: test_tags_2.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -124,6 +135,7 @@ fn files_right_tag() {
         database_path: Some(db_path.clone()),
         command: Command::Files {
             tags: Some(vec!["test4".into()]),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -141,6 +153,7 @@ fn files_neither_tag() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -151,6 +164,7 @@ fn files_neither_tag() {
         command: Command::Tag {
             file_path: tag_file_2.clone(),
             tags: test_tags_2.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -160,6 +174,7 @@ fn files_neither_tag() {
         database_path: Some(db_path.clone()),
         command: Command::Files {
             tags: Some([&test_tags_1[..], &test_tags_2[..]].concat().clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -177,6 +192,7 @@ fn get_files() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -187,6 +203,7 @@ fn get_files() {
         command: Command::Tag {
             file_path: tag_file_2.clone(),
             tags: test_tags_2.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -194,7 +211,10 @@ fn get_files() {
 
     let args = Args {
         database_path: Some(db_path.clone()),
-        command: Command::Files { tags: None },
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args).unwrap();
     assert_eq!(
@@ -228,7 +248,10 @@ fn get_files() {
 
     let args = Args {
         database_path: Some(db_path.clone()),
-        command: Command::Files { tags: None },
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args).unwrap();
     assert_eq!(None, out);
@@ -244,6 +267,7 @@ fn no_tags_specified() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -251,7 +275,10 @@ fn no_tags_specified() {
 
     let args = Args {
         database_path: Some(db_path.clone()),
-        command: Command::Files { tags: Some(vec![]) },
+        command: Command::Files {
+            tags: Some(vec![]),
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args);
     assert!(matches!(out, Err(AppError::NoTagsSpecified)));
@@ -0,0 +1,177 @@
+mod common;
+
+use crate::common::{file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, ExportFormat, db::ImportMode, entrypoint};
+use std::path::{Path, PathBuf};
+
+fn export(db_path: &Path, document_path: &Path) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Export {
+            format: ExportFormat::Json,
+        },
+    };
+    let out = entrypoint(args).unwrap().unwrap();
+    std::fs::write(document_path, out).unwrap();
+}
+
+fn import(db_path: &Path, document_path: &Path, mode: ImportMode) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Import {
+            document_path: document_path.to_path_buf(),
+            mode,
+        },
+    };
+    entrypoint(args).unwrap()
+}
+
+#[test]
+fn import_into_empty_database() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let document_path = temp_dir.path().join("tags.json");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    export(&db_path, &document_path);
+
+    std::fs::remove_file(&db_path).unwrap();
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file_1.clone(),
+            tags: vec!["other".into()],
+            create: true,
+        },
+    };
+    entrypoint(args).unwrap();
+
+    let out = import(&db_path, &document_path, ImportMode::Merge);
+    assert_eq!(
+        Some(
+            "Added: temp_tag_file_2\nUpdated: temp_tag_file_1 (+test,test2,test3)\nUnchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        Some("other,test,test2,test3".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+    assert_eq!(
+        Some(test_tags_2.join(",")),
+        file_tags(&db_path, &tag_file_2)
+    );
+
+    let out = import(&db_path, &document_path, ImportMode::Merge);
+    assert_eq!(Some("Unchanged: 2 files".into()), out);
+}
+
+#[test]
+fn import_replace() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let document_path = temp_dir.path().join("tags.json");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    export(&db_path, &document_path);
+    tag(&db_path, &tag_file_1, &["other"]);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    let out = import(&db_path, &document_path, ImportMode::Replace);
+    assert_eq!(
+        Some(
+            "Added: temp_tag_file_1\n\
+            Deleted tag: other\n\
+            Deleted tag: test4\n\
+            Deleted tag: test5\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        Some(test_tags_1.join(",")),
+        file_tags(&db_path, &tag_file_1)
+    );
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn import_dry_run() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let document_path = temp_dir.path().join("tags.json");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    export(&db_path, &document_path);
+
+    std::fs::remove_file(&db_path).unwrap();
+    tag(&db_path, &tag_file_1, &["other"]);
+
+    let out = import(&db_path, &document_path, ImportMode::DryRun);
+    assert_eq!(
+        Some(
+            "Would add: temp_tag_file_2\nWould update: temp_tag_file_1 (+test,test2,test3)\nUnchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(Some("other".into()), file_tags(&db_path, &tag_file_1));
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn import_matches_moved_file_by_hash() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    let document_path = temp_dir.path().join("tags.json");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    export(&db_path, &document_path);
+
+    std::fs::remove_file(&db_path).unwrap();
+    let moved_file: PathBuf = temp_dir.path().join("moved_file");
+    std::fs::rename(&tag_file_1, &moved_file).unwrap();
+    tag(&db_path, &moved_file, &["other"]);
+
+    let out = import(&db_path, &document_path, ImportMode::Merge);
+    assert_eq!(
+        Some("Updated: moved_file (+test,test2,test3)\nUnchanged: 0 files".into()),
+        out
+    );
+    assert_eq!(
+        Some("other,test,test2,test3".into()),
+        file_tags(&db_path, &moved_file)
+    );
+}
+
+#[test]
+fn import_invalid_document() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let document_path = temp_dir.path().join("tags.json");
+    std::fs::write(&document_path, "not a json").unwrap();
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Import {
+            document_path: document_path.clone(),
+            mode: ImportMode::Merge,
+        },
+    };
+    assert!(matches!(
+        entrypoint(args),
+        Err(AppError::InvalidDocument(_))
+    ));
+
+    std::fs::write(&document_path, r#"{"version": 2, "tags": [], "files": []}"#).unwrap();
+    let args = Args {
+        database_path: Some(db_path),
+        command: Command::Import {
+            document_path,
+            mode: ImportMode::Merge,
+        },
+    };
+    assert!(matches!(
+        entrypoint(args),
+        Err(AppError::UnsupportedDocumentVersion(2))
+    ));
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use crate::common::{file_tags, two_files_multiple_tags_prepare};
+use anytaggy::{
+    AppError, Args, Command,
+    db::{DatabaseError, ImportMode},
+    entrypoint,
+};
+use std::path::Path;
+
+const TMSU_SCHEMA: &str = "
+    CREATE TABLE tag (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE file (
+        id INTEGER PRIMARY KEY,
+        directory TEXT NOT NULL,
+        name TEXT NOT NULL,
+        fingerprint TEXT NOT NULL,
+        mod_time DATETIME NOT NULL,
+        size INTEGER NOT NULL,
+        is_dir BOOLEAN NOT NULL
+    );
+    CREATE TABLE value (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE file_tag (file_id INTEGER NOT NULL, tag_id INTEGER NOT NULL, value_id INTEGER NOT NULL);
+    CREATE TABLE implication (
+        tag_id INTEGER NOT NULL,
+        value_id INTEGER NOT NULL,
+        implied_tag_id INTEGER NOT NULL,
+        implied_value_id INTEGER NOT NULL
+    );
+";
+
+fn create_tmsu_database(root: &Path, absolute_file: &Path) -> std::path::PathBuf {
+    std::fs::create_dir(root.join(".tmsu")).unwrap();
+    let tmsu_path = root.join(".tmsu").join("db");
+    let connection = rusqlite::Connection::open(&tmsu_path).unwrap();
+    connection.execute_batch(TMSU_SCHEMA).unwrap();
+    connection
+        .execute_batch(
+            "INSERT INTO tag VALUES (1, 'photo'), (2, 'year'), (3, 'media'), (4, 'untagged');
+            INSERT INTO value VALUES (1, '2017');
+            INSERT INTO file VALUES (1, '.', 'temp_tag_file_1', '', '', 0, 0);
+            INSERT INTO file VALUES (3, '.', 'missing_file', '', '', 0, 0);
+            INSERT INTO file VALUES (4, '/', 'directory', '', '', 0, 1);
+            INSERT INTO file VALUES (5, '.', 'not_tagged', '', '', 0, 0);
+            INSERT INTO file_tag VALUES (1, 1, 0), (1, 2, 1), (3, 1, 0), (4, 1, 0);
+            INSERT INTO implication VALUES (1, 0, 3, 0);",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "INSERT INTO file VALUES (2, ?1, ?2, '', '', 0, 0)",
+            (
+                absolute_file.parent().unwrap().to_str().unwrap(),
+                absolute_file.file_name().unwrap().to_str().unwrap(),
+            ),
+        )
+        .unwrap();
+    connection
+        .execute_batch("INSERT INTO file_tag VALUES (2, 3, 0);")
+        .unwrap();
+    tmsu_path
+}
+
+fn import_tmsu(
+    db_path: &Path,
+    tmsu_database_path: &Path,
+    mode: ImportMode,
+) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::ImportTmsu {
+            tmsu_database_path: tmsu_database_path.to_path_buf(),
+            mode,
+        },
+    };
+    entrypoint(args)
+}
+
+fn init(db_path: &Path) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Init { directory: None },
+    };
+    entrypoint(args).unwrap();
+}
+
+#[test]
+fn import_tmsu_files_and_values() {
+    let (db_path, tag_file_1, tag_file_2, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let tmsu_path = create_tmsu_database(temp_dir.path(), &tag_file_2);
+    init(&db_path);
+
+    let out = import_tmsu(&db_path, &tmsu_path, ImportMode::Merge)
+        .unwrap()
+        .unwrap();
+    let root = temp_dir.path().display();
+    assert_eq!(
+        format!(
+            "Missing: {root}/missing_file\n\
+            Unsupported directory: /directory\n\
+            Unsupported implication: photo -> media\n\
+            Added: temp_tag_file_1\n\
+            Added: temp_tag_file_2\n\
+            Unchanged: 0 files"
+        ),
+        out
+    );
+    assert_eq!(
+        Some("photo,year=2017".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+    assert_eq!(Some("media".into()), file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn import_tmsu_dry_run() {
+    let (db_path, tag_file_1, tag_file_2, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let tmsu_path = create_tmsu_database(temp_dir.path(), &tag_file_2);
+    init(&db_path);
+
+    let out = import_tmsu(&db_path, &tmsu_path, ImportMode::DryRun)
+        .unwrap()
+        .unwrap();
+    assert!(out.contains("Would add: temp_tag_file_1"));
+    assert_eq!(None, file_tags(&db_path, &tag_file_1));
+}
+
+#[test]
+fn import_tmsu_rejects_other_databases() {
+    let (db_path, _, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+    init(&db_path);
+
+    let err = import_tmsu(&db_path, &db_path, ImportMode::Merge).unwrap_err();
+    assert!(matches!(err, AppError::Database(DatabaseError::NotTmsu)));
+}
+
+#[test]
+fn import_tmsu_relative_database_path() {
+    let (db_path, tag_file_1, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    create_tmsu_database(temp_dir.path(), &temp_dir.path().join("unknown"));
+    init(&db_path);
+    std::env::set_current_dir(temp_dir.path().join(".tmsu")).unwrap();
+
+    import_tmsu(&db_path, Path::new("db"), ImportMode::Merge).unwrap();
+    assert_eq!(
+        Some("photo,year=2017".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use crate::common::create_random_file;
+use anytaggy::{AppError, Args, Command, DATABASE_FILENAME, DatabaseSources, entrypoint};
+use std::fs::create_dir;
+use temp_dir::TempDir;
+
+#[test]
+fn init_database_in_current_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let args = Args {
+        database_path: None,
+        command: Command::Init { directory: None },
+    };
+    let out = entrypoint(args).unwrap();
+    let db_path = temp_dir.path().join(DATABASE_FILENAME);
+    assert_eq!(
+        Some(format!("Initialized database in {}", db_path.display())),
+        out
+    );
+    assert!(db_path.is_file());
+}
+
+#[test]
+fn init_database_in_new_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let directory = temp_dir.path().join("nested").join("folder");
+
+    let args = Args {
+        database_path: None,
+        command: Command::Init {
+            directory: Some(directory.clone()),
+        },
+    };
+    entrypoint(args).unwrap();
+
+    let args = Args {
+        database_path: Some(directory.join(DATABASE_FILENAME)),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+}
+
+#[test]
+fn init_existing_database() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let args = Args {
+        database_path: None,
+        command: Command::Init {
+            directory: Some(temp_dir.path().to_path_buf()),
+        },
+    };
+    entrypoint(args).unwrap();
+
+    let args = Args {
+        database_path: None,
+        command: Command::Init {
+            directory: Some(temp_dir.path().to_path_buf()),
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(out, Err(AppError::DatabaseAlreadyExists(_))));
+}
+
+#[test]
+fn tag_without_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join(DATABASE_FILENAME);
+    let tag_file = create_random_file(temp_dir.path(), "temp_tag_file");
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file,
+            tags: vec!["test".into()],
+            create: false,
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(out, Err(AppError::DatabaseNotFound)));
+    assert!(!db_path.exists());
+}
+
+#[test]
+fn paths_resolved_against_root() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join(DATABASE_FILENAME);
+    let subfolder = temp_dir.path().join("folder");
+    create_dir(&subfolder).unwrap();
+    let tag_file = create_random_file(&subfolder, "temp_tag_file");
+
+    let args = Args {
+        database_path: None,
+        command: Command::Init {
+            directory: Some(temp_dir.path().to_path_buf()),
+        },
+    };
+    entrypoint(args).unwrap();
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file.clone(),
+            tags: vec!["test".into()],
+            create: false,
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+
+    let connection = rusqlite::Connection::open(&db_path).unwrap();
+    let stored_path: String = connection
+        .query_row("SELECT path FROM files", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!("folder/temp_tag_file", stored_path);
+
+    // Same file reached through a different path
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: Some(subfolder.join("..").join("folder").join("temp_tag_file")),
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some("test".into()), out);
+
+    let args = Args {
+        database_path: Some(db_path),
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some(tag_file.display().to_string()), out);
+}
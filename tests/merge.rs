@@ -0,0 +1,94 @@
+mod common;
+
+use crate::common::{create_random_file, file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{Args, Command, DatabaseSources, db::MergePreference, entrypoint};
+use std::path::Path;
+
+fn merge(db_path: &Path, other_database_path: &Path, prefer: MergePreference) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Merge {
+            other_database_path: other_database_path.to_path_buf(),
+            prefer,
+        },
+    };
+    entrypoint(args).unwrap()
+}
+
+#[test]
+fn merge_unites_tags() {
+    let (db_path, tag_file_1, tag_file_2, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let tag_file_3 = create_random_file(temp_dir.path(), "temp_tag_file_3");
+    let other_db_path = temp_dir.path().join("other.db");
+    tag(&db_path, &tag_file_1, &["a", "b"]);
+    tag(&db_path, &tag_file_3, &["e"]);
+    tag(&other_db_path, &tag_file_1, &["b", "c"]);
+    tag(&other_db_path, &tag_file_2, &["d"]);
+
+    let out = merge(&db_path, &other_db_path, MergePreference::Ours);
+    assert_eq!(
+        Some(
+            "Added: temp_tag_file_2\nOnly in ours: temp_tag_file_3\nUpdated: temp_tag_file_1 (+c)\nUnchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(Some("a,b,c".into()), file_tags(&db_path, &tag_file_1));
+    assert_eq!(Some("d".into()), file_tags(&db_path, &tag_file_2));
+    assert_eq!(Some("e".into()), file_tags(&db_path, &tag_file_3));
+
+    // Other database is left as is
+    assert_eq!(Some("b,c".into()), file_tags(&other_db_path, &tag_file_1));
+
+    let out = merge(&db_path, &other_db_path, MergePreference::Ours);
+    assert_eq!(
+        Some("Only in ours: temp_tag_file_3\nUnchanged: 2 files".into()),
+        out
+    );
+}
+
+#[test]
+fn merge_prefers_their_removals() {
+    let (db_path, tag_file_1, tag_file_2, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let other_db_path = temp_dir.path().join("other.db");
+    tag(&db_path, &tag_file_1, &["a", "b"]);
+    tag(&db_path, &tag_file_2, &["e"]);
+    tag(&other_db_path, &tag_file_1, &["b", "c"]);
+
+    let out = merge(&db_path, &other_db_path, MergePreference::Theirs);
+    assert_eq!(
+        Some(
+            "Removed: temp_tag_file_2\nUpdated: temp_tag_file_1 (+c,-a)\nDeleted tag: a\nDeleted tag: e\nUnchanged: 0 files".into()
+        ),
+        out
+    );
+    assert_eq!(Some("b,c".into()), file_tags(&db_path, &tag_file_1));
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+
+    // Tags left without files are removed and reported
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    assert_eq!(Some("b,c".into()), entrypoint(args).unwrap());
+}
+
+#[test]
+fn merge_matches_moved_files_by_contents() {
+    let (db_path, tag_file_1, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let other_db_path = temp_dir.path().join("other.db");
+    tag(&other_db_path, &tag_file_1, &["theirs"]);
+    let moved_file = temp_dir.path().join("moved_file");
+    std::fs::rename(&tag_file_1, &moved_file).unwrap();
+    tag(&db_path, &moved_file, &["ours"]);
+
+    let out = merge(&db_path, &other_db_path, MergePreference::Ours);
+    assert_eq!(
+        Some("Updated: moved_file (+theirs)\nUnchanged: 0 files".into()),
+        out
+    );
+    assert_eq!(Some("ours,theirs".into()), file_tags(&db_path, &moved_file));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use crate::common::{tag, two_files_multiple_tags_prepare};
+use anytaggy::{Args, Command, DatabaseSources, ExportFormat, entrypoint};
+use std::path::Path;
+
+fn run(db_path: &Path, command: Command) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command,
+    };
+    entrypoint(args).unwrap()
+}
+
+/// Same as file removed while watching
+fn set_missing(db_path: &Path, name: &str) {
+    let connection = rusqlite::Connection::open(db_path).unwrap();
+    connection
+        .execute("UPDATE files SET missing = 1 WHERE name = ?1", [name])
+        .unwrap();
+}
+
+#[test]
+fn missing_files_are_listed_separately() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    set_missing(&db_path, "temp_tag_file_1");
+
+    let out = run(
+        &db_path,
+        Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
+    );
+    assert_eq!(Some(tag_file_2.display().to_string()), out);
+    let out = run(
+        &db_path,
+        Command::Files {
+            tags: Some(vec!["test3".into()]),
+            sources: DatabaseSources::default(),
+        },
+    );
+    assert_eq!(Some(tag_file_2.display().to_string()), out);
+    let out = run(
+        &db_path,
+        Command::Export {
+            format: ExportFormat::Json,
+        },
+    )
+    .unwrap();
+    assert!(!out.contains("temp_tag_file_1"));
+
+    let out = run(&db_path, Command::Missing { prune: false });
+    assert_eq!(Some("temp_tag_file_1".into()), out);
+}
+
+#[test]
+fn prune_missing_files() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    set_missing(&db_path, "temp_tag_file_1");
+
+    let out = run(&db_path, Command::Missing { prune: true });
+    assert_eq!(
+        Some("Removed: temp_tag_file_1\nRemoved 1 missing files".into()),
+        out
+    );
+    assert_eq!(None, run(&db_path, Command::Missing { prune: false }));
+
+    // Tags are kept, even when no other file has them
+    let out = run(
+        &db_path,
+        Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    );
+    assert_eq!(Some("test,test2,test3,test4,test5".into()), out);
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "fuse")]
+
+mod common;
+
+use crate::common::{file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{Args, Command, DatabaseSources, entrypoint};
+use std::{
+    path::{Path, PathBuf},
+    process,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+fn mount(db_path: &Path, mountpoint: &Path) -> JoinHandle<()> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Mount {
+            mountpoint: mountpoint.to_path_buf(),
+        },
+    };
+    let handle = thread::spawn(move || assert_eq!(None, entrypoint(args).unwrap()));
+    for _ in 0..100 {
+        if mountpoint.join("tags").is_dir() {
+            return handle;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("filesystem was not mounted");
+}
+
+fn unmount(mountpoint: &Path, handle: JoinHandle<()>) {
+    let unmounted = [
+        ("fusermount3", "-u"),
+        ("fusermount", "-u"),
+        ("umount", "-l"),
+    ]
+    .iter()
+    .any(|(program, flag)| {
+        process::Command::new(program)
+            .args([flag.as_ref(), mountpoint.as_os_str()])
+            .status()
+            .is_ok_and(|status| status.success())
+    });
+    assert!(unmounted);
+    handle.join().unwrap();
+}
+
+fn entries(directory: &Path) -> Vec<String> {
+    let mut entries: Vec<_> = std::fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn mount_exposes_tags() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let mountpoint = temp_dir.path().join("mnt");
+    std::fs::create_dir(&mountpoint).unwrap();
+
+    let handle = mount(&db_path, &mountpoint);
+    let tags = mountpoint.join("tags");
+    assert_eq!(
+        vec!["test", "test2", "test3", "test4", "test5"],
+        entries(&tags)
+    );
+    assert_eq!(
+        vec![
+            "temp_tag_file_1",
+            "temp_tag_file_2",
+            "test",
+            "test2",
+            "test4",
+            "test5"
+        ],
+        entries(&tags.join("test3"))
+    );
+    // Only tags of files in the directory are listed
+    assert_eq!(
+        vec!["temp_tag_file_1", "test2", "test3"],
+        entries(&tags.join("test"))
+    );
+    assert!(!tags.join("test").join("test4").exists());
+    assert_eq!(
+        tag_file_2,
+        std::fs::read_link(tags.join("test3").join("test4").join("temp_tag_file_2")).unwrap()
+    );
+    assert!(!tags.join("missing").exists());
+
+    std::fs::create_dir(tags.join("new")).unwrap();
+    assert!(tags.join("new").is_dir());
+    assert!(std::fs::create_dir(tags.join("test")).is_err());
+
+    std::fs::remove_file(tags.join("test").join("test3").join("temp_tag_file_1")).unwrap();
+    unmount(&mountpoint, handle);
+
+    assert_eq!(Some("test,test2".into()), file_tags(&db_path, &tag_file_1));
+    assert!(entries(&mountpoint).is_empty());
+    let args = Args {
+        database_path: Some(db_path),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    assert_eq!(
+        Some("test,test2,test3,test4,test5,new".into()),
+        entrypoint(args).unwrap()
+    );
+}
+
+#[test]
+fn mount_links_duplicate_names() {
+    let (db_path, tag_file_1, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let nested = temp_dir.path().join("nested");
+    std::fs::create_dir(&nested).unwrap();
+    let nested_file: PathBuf = nested.join("temp_tag_file_1");
+    std::fs::write(&nested_file, "other contents").unwrap();
+    tag(&db_path, &tag_file_1, &["same"]);
+    tag(&db_path, &nested_file, &["same"]);
+    let mountpoint = temp_dir.path().join("mnt");
+    std::fs::create_dir(&mountpoint).unwrap();
+
+    let handle = mount(&db_path, &mountpoint);
+    let same = mountpoint.join("tags").join("same");
+    assert_eq!(
+        vec!["temp_tag_file_1", "temp_tag_file_1 (2)"],
+        entries(&same)
+    );
+    assert_eq!(
+        nested_file,
+        std::fs::read_link(same.join("temp_tag_file_1")).unwrap()
+    );
+    unmount(&mountpoint, handle);
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use crate::common::create_random_file;
+use anytaggy::{AppError, Args, Command, DATABASE_FILENAME, DatabaseSources, entrypoint};
+use std::{
+    fs::create_dir,
+    path::{Path, PathBuf},
+};
+use temp_dir::TempDir;
+
+/// Creates database with one tagged file in the subdirectory, returns database and file paths
+fn tagged_database(parent: &Path, name: &str, tags: &[&str]) -> (PathBuf, PathBuf) {
+    let directory = parent.join(name);
+    create_dir(&directory).unwrap();
+    let db_path = directory.join(DATABASE_FILENAME);
+    let tag_file = create_random_file(&directory, &format!("{name}_file"));
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file.clone(),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            create: true,
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+
+    (db_path, tag_file)
+}
+
+#[test]
+fn files_across_databases() {
+    let temp_dir = TempDir::new().unwrap();
+    let (db_path_a, tag_file_a) = tagged_database(temp_dir.path(), "drive_a", &["test", "a"]);
+    let (db_path_b, tag_file_b) = tagged_database(temp_dir.path(), "drive_b", &["test", "b"]);
+
+    let args = Args {
+        database_path: Some(db_path_a.clone()),
+        command: Command::Files {
+            tags: Some(vec!["test".into()]),
+            sources: DatabaseSources {
+                databases: vec![db_path_b.clone()],
+                discover: None,
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(
+        Some(format!(
+            "{}\n{}",
+            tag_file_a.display(),
+            tag_file_b.display()
+        )),
+        out
+    );
+
+    let args = Args {
+        database_path: Some(db_path_a),
+        command: Command::Files {
+            tags: Some(vec!["b".into()]),
+            sources: DatabaseSources {
+                databases: vec![db_path_b],
+                discover: None,
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some(tag_file_b.display().to_string()), out);
+}
+
+#[test]
+fn tags_across_databases() {
+    let temp_dir = TempDir::new().unwrap();
+    let (db_path_a, _) = tagged_database(temp_dir.path(), "drive_a", &["test", "a"]);
+    let (db_path_b, tag_file_b) = tagged_database(temp_dir.path(), "drive_b", &["test", "b"]);
+
+    let args = Args {
+        database_path: Some(db_path_a.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources {
+                databases: vec![db_path_b.clone()],
+                discover: None,
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some("a,b,test".into()), out);
+
+    // File is looked up in the database it belongs to
+    let args = Args {
+        database_path: Some(db_path_a),
+        command: Command::Tags {
+            file_path: Some(tag_file_b),
+            sources: DatabaseSources {
+                databases: vec![db_path_b],
+                discover: None,
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some("test,b".into()), out);
+}
+
+#[test]
+fn discover_databases() {
+    let temp_dir = TempDir::new().unwrap();
+    let (db_path_a, tag_file_a) = tagged_database(temp_dir.path(), "drive_a", &["test"]);
+    let (_, tag_file_b) = tagged_database(temp_dir.path(), "drive_b", &["test"]);
+    let (_, tag_file_c) = tagged_database(&temp_dir.path().join("drive_b"), "nested", &["test"]);
+
+    // Main database is discovered too, but queried only once
+    let args = Args {
+        database_path: Some(db_path_a),
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources {
+                databases: vec![],
+                discover: Some(temp_dir.path().to_path_buf()),
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(
+        Some(format!(
+            "{}\n{}\n{}",
+            tag_file_a.display(),
+            tag_file_b.display(),
+            tag_file_c.display()
+        )),
+        out
+    );
+}
+
+#[test]
+fn missing_additional_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let (db_path_a, _) = tagged_database(temp_dir.path(), "drive_a", &["test"]);
+
+    let args = Args {
+        database_path: Some(db_path_a),
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources {
+                databases: vec![temp_dir.path().join("missing.db")],
+                discover: None,
+            },
+        },
+    };
+    let out = entrypoint(args);
+    assert!(matches!(out, Err(AppError::DatabaseNotFound)));
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use crate::common::{create_random_file, tag};
+use anytaggy::{
+    Args, Command, DATABASE_FILENAME, DatabaseSources, DbCommand, db::Setting, entrypoint,
+};
+use std::{
+    fs::{Permissions, create_dir},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+use temp_dir::TempDir;
+
+/// Creates parent database with nested child database, returns their paths
+fn nested_databases_prepare(temp_dir: &TempDir) -> (PathBuf, PathBuf) {
+    let child_dir = temp_dir.path().join("project");
+    create_dir(&child_dir).unwrap();
+
+    for directory in [temp_dir.path().to_path_buf(), child_dir.clone()] {
+        let args = Args {
+            database_path: None,
+            command: Command::Init {
+                directory: Some(directory),
+            },
+        };
+        entrypoint(args).unwrap();
+    }
+
+    (
+        temp_dir.path().join(DATABASE_FILENAME),
+        child_dir.join(DATABASE_FILENAME),
+    )
+}
+
+fn include_nested(db_path: &Path, enabled: bool) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Db {
+            command: DbCommand::Set {
+                setting: Setting::IncludeNested,
+                enabled,
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+}
+
+fn files(db_path: &Path) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Files {
+            tags: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    entrypoint(args).unwrap()
+}
+
+#[test]
+fn setting_disabled_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let (parent_db, child_db) = nested_databases_prepare(&temp_dir);
+    let parent_file = create_random_file(temp_dir.path(), "parent_file");
+    let child_file = create_random_file(child_db.parent().unwrap(), "child_file");
+
+    let args = Args {
+        database_path: Some(parent_db.clone()),
+        command: Command::Db {
+            command: DbCommand::Get {
+                setting: Setting::IncludeNested,
+            },
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some("false".into()), out);
+
+    tag(&parent_db, &parent_file, &["test"]);
+    tag(&child_db, &child_file, &["test"]);
+
+    assert_eq!(Some(parent_file.display().to_string()), files(&parent_db));
+    assert_eq!(Some(child_file.display().to_string()), files(&child_db));
+}
+
+#[test]
+fn nested_database_included_in_queries() {
+    let temp_dir = TempDir::new().unwrap();
+    let (parent_db, child_db) = nested_databases_prepare(&temp_dir);
+    let parent_file = create_random_file(temp_dir.path(), "parent_file");
+    let child_file = create_random_file(child_db.parent().unwrap(), "child_file");
+
+    tag(&parent_db, &parent_file, &["parent"]);
+    tag(&child_db, &child_file, &["child"]);
+    include_nested(&parent_db, true);
+
+    assert_eq!(
+        Some(format!(
+            "{}\n{}",
+            parent_file.display(),
+            child_file.display()
+        )),
+        files(&parent_db)
+    );
+
+    let args = Args {
+        database_path: Some(parent_db.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(Some("child,parent".into()), out);
+
+    include_nested(&parent_db, false);
+    assert_eq!(Some(parent_file.display().to_string()), files(&parent_db));
+}
+
+#[test]
+fn nested_database_search_stops_at_nested_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let (parent_db, child_db) = nested_databases_prepare(&temp_dir);
+    let child_dir = child_db.parent().unwrap();
+    let grandchild_dir = child_dir.join("module");
+    create_dir(&grandchild_dir).unwrap();
+    let args = Args {
+        database_path: None,
+        command: Command::Init {
+            directory: Some(grandchild_dir.clone()),
+        },
+    };
+    entrypoint(args).unwrap();
+    let child_file = create_random_file(child_dir, "child_file");
+    let grandchild_file = create_random_file(&grandchild_dir, "grandchild_file");
+    tag(&child_db, &child_file, &["child"]);
+    tag(
+        &grandchild_dir.join(DATABASE_FILENAME),
+        &grandchild_file,
+        &["grandchild"],
+    );
+
+    let unreadable_dir = temp_dir.path().join("unreadable");
+    create_dir(&unreadable_dir).unwrap();
+    std::fs::set_permissions(&unreadable_dir, Permissions::from_mode(0o000)).unwrap();
+    include_nested(&parent_db, true);
+
+    // Database nested in child one is only included in queries of the child
+    let out = files(&parent_db);
+    std::fs::set_permissions(&unreadable_dir, Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(Some(child_file.display().to_string()), out);
+}
+
+#[test]
+fn tag_innermost_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let (parent_db, child_db) = nested_databases_prepare(&temp_dir);
+    let child_file = create_random_file(child_db.parent().unwrap(), "child_file");
+    include_nested(&parent_db, true);
+
+    tag(&parent_db, &child_file, &["test"]);
+    assert_eq!(Some(child_file.display().to_string()), files(&child_db));
+
+    let args = Args {
+        database_path: Some(parent_db.clone()),
+        command: Command::Untag {
+            file_path: child_file.clone(),
+            tags: vec!["test".into()],
+        },
+    };
+    let out = entrypoint(args).unwrap();
+    assert_eq!(None, out);
+    assert_eq!(None, files(&child_db));
+    assert_eq!(None, files(&parent_db));
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use crate::common::{create_random_file, file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{
+    AppError, Args, Command, DbCommand,
+    db::{DatabaseError, Setting},
+    entrypoint,
+};
+use std::path::{Path, PathBuf};
+
+fn text_sidecar(db_path: &Path, enabled: bool) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Db {
+            command: DbCommand::Set {
+                setting: Setting::TextSidecar,
+                enabled,
+            },
+        },
+    };
+    entrypoint(args).unwrap();
+}
+
+fn rebuild(db_path: &Path, text_path: Option<PathBuf>) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::RebuildFromText { text_path },
+    };
+    entrypoint(args)
+}
+
+#[test]
+fn sidecar_is_written_on_every_change() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    let text_path = db_path.with_extension("txt");
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    assert!(!text_path.exists());
+
+    text_sidecar(&db_path, true);
+    assert_eq!(
+        "temp_tag_file_2\ttest3,test4,test5\n",
+        std::fs::read_to_string(&text_path).unwrap()
+    );
+
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    assert_eq!(
+        "temp_tag_file_1\ttest,test2,test3\ntemp_tag_file_2\ttest3,test4,test5\n",
+        std::fs::read_to_string(&text_path).unwrap()
+    );
+
+    text_sidecar(&db_path, false);
+    tag(&db_path, &tag_file_1, &["test6"]);
+    assert_eq!(
+        "temp_tag_file_1\ttest,test2,test3\ntemp_tag_file_2\ttest3,test4,test5\n",
+        std::fs::read_to_string(&text_path).unwrap()
+    );
+}
+
+#[test]
+fn sidecar_failure_keeps_change() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    let text_path = db_path.with_extension("txt");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    text_sidecar(&db_path, true);
+
+    // Sidecar can't replace a directory
+    std::fs::remove_file(&text_path).unwrap();
+    std::fs::create_dir(&text_path).unwrap();
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tag {
+            file_path: tag_file_2.clone(),
+            tags: test_tags_2.clone(),
+            create: false,
+        },
+    };
+    assert_eq!(None, entrypoint(args).unwrap());
+    assert_eq!(
+        Some(test_tags_2.join(",")),
+        file_tags(&db_path, &tag_file_2)
+    );
+}
+
+#[test]
+fn sidecar_escapes_separators() {
+    let (db_path, tag_file_1, _, _, _, temp_dir) = two_files_multiple_tags_prepare();
+    let tab_file = create_random_file(temp_dir.path(), "tab\tfile");
+    tag(&db_path, &tag_file_1, &["a,b", "c\\d"]);
+    tag(&db_path, &tab_file, &["e"]);
+    text_sidecar(&db_path, true);
+
+    let text_path = db_path.with_extension("txt");
+    assert_eq!(
+        "tab\\tfile\te\ntemp_tag_file_1\ta\\,b,c\\\\d\n",
+        std::fs::read_to_string(&text_path).unwrap()
+    );
+
+    let out = rebuild(&db_path, None).unwrap().unwrap();
+    assert_eq!("Unchanged: 2 files", out);
+    assert_eq!(Some("a,b,c\\d".into()), file_tags(&db_path, &tag_file_1));
+}
+
+#[test]
+fn rebuild_applies_edited_sidecar() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let tag_file_3 = create_random_file(temp_dir.path(), "temp_tag_file_3");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    text_sidecar(&db_path, true);
+
+    let text_path = db_path.with_extension("txt");
+    std::fs::write(
+        &text_path,
+        "temp_tag_file_1\ttest,merged\ntemp_tag_file_3\tnew\nmissing_file\tgone\n",
+    )
+    .unwrap();
+
+    let out = rebuild(&db_path, None).unwrap().unwrap();
+    assert_eq!(
+        "Missing: missing_file\nRemoved: temp_tag_file_2\nUpdated: temp_tag_file_1 (+merged,-test2,-test3)\nAdded: temp_tag_file_3\nUnchanged: 0 files",
+        out
+    );
+    assert_eq!(Some("merged,test".into()), file_tags(&db_path, &tag_file_1));
+    assert_eq!(Some("new".into()), file_tags(&db_path, &tag_file_3));
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+
+    // Sidecar is rewritten from rebuilt database
+    assert_eq!(
+        "temp_tag_file_1\tmerged,test\ntemp_tag_file_3\tnew\n",
+        std::fs::read_to_string(&text_path).unwrap()
+    );
+}
+
+#[test]
+fn rebuild_rejects_invalid_sidecar() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    let text_path = temp_dir.path().join("tags.txt");
+    std::fs::write(&text_path, "temp_tag_file_1\ttest\nno tags here\n").unwrap();
+
+    let err = rebuild(&db_path, Some(text_path)).unwrap_err();
+    assert!(matches!(
+        err,
+        AppError::Database(DatabaseError::InvalidTextSidecar(2))
+    ));
+    assert_eq!(
+        Some("test,test2,test3".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+}
@@ -1,7 +1,7 @@
 mod common;
 
 use crate::common::two_files_multiple_tags_prepare;
-use anytaggy::{AppError, Args, Command, db::DatabaseError, entrypoint};
+use anytaggy::{AppError, Args, Command, DatabaseSources, db::DatabaseError, entrypoint};
 
 #[test]
 fn no_rm_tags_database() {
@@ -24,6 +24,7 @@ fn no_rm_tags_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -46,6 +47,7 @@ fn rm_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -55,6 +57,7 @@ fn rm_tags() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -73,6 +76,7 @@ fn rm_tags() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -82,6 +86,7 @@ fn rm_tags() {
         database_path: Some(db_path.clone()),
         command: Command::Files {
             tags: Some(test_tags),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -97,6 +102,7 @@ fn no_such_tag() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -0,0 +1,144 @@
+mod common;
+
+use crate::common::{tag, two_files_multiple_tags_prepare};
+use std::{
+    io::Write,
+    path::Path,
+    process::{self, Stdio},
+};
+
+/// Runs `anytaggy rpc` with requests on its stdin, returns response lines
+fn rpc(db_path: &Path, requests: &[&str]) -> Vec<String> {
+    let mut child = process::Command::new(env!("CARGO_BIN_EXE_anytaggy"))
+        .arg("--database-path")
+        .arg(db_path)
+        .arg("rpc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for request in requests {
+        writeln!(stdin, "{request}").unwrap();
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn rpc_methods() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    let responses = rpc(
+        &db_path,
+        &[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tags"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "files", "params": {"tags": ["test3", "test4"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": "tag", "method": "tag", "params": {"path": "temp_tag_file_1", "tags": ["new"]}}"#,
+            r#"{"jsonrpc": "2.0", "method": "untag", "params": {"path": "temp_tag_file_1", "tags": ["test"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "file", "params": {"path": "temp_tag_file_1"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "untag", "params": {"path": "temp_tag_file_2", "tags": ["test4"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "files", "params": {"tags": ["test3"]}}"#,
+        ],
+    );
+    assert_eq!(
+        vec![
+            r#"{"id":1,"jsonrpc":"2.0","result":["test","test2","test3","test4","test5"]}"#
+                .to_string(),
+            r#"{"id":2,"jsonrpc":"2.0","result":["temp_tag_file_2"]}"#.to_string(),
+            r#"{"id":"tag","jsonrpc":"2.0","result":{"mime_type":null,"name":"temp_tag_file_1","path":"temp_tag_file_1","tags":["test","test2","test3","new"]}}"#
+                .to_string(),
+            r#"{"id":3,"jsonrpc":"2.0","result":{"mime_type":null,"name":"temp_tag_file_1","path":"temp_tag_file_1","tags":["test2","test3","new"]}}"#
+                .to_string(),
+            r#"{"id":4,"jsonrpc":"2.0","result":null}"#.to_string(),
+            r#"{"id":5,"jsonrpc":"2.0","result":["temp_tag_file_1","temp_tag_file_2"]}"#
+                .to_string(),
+        ],
+        responses
+    );
+}
+
+#[test]
+fn rpc_errors() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let responses = rpc(
+        &db_path,
+        &[
+            "not json",
+            r#"{"id": 1, "method": "tags"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "other"}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "file", "params": {"file": "temp_tag_file_1"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "file", "params": {"path": "missing"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "untag", "params": {"path": "temp_tag_file_1", "tags": ["other"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "tag", "params": {"path": "temp_tag_file_1", "tags": ["new", " "]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "untag", "params": {"path": "temp_tag_file_1", "tags": ["test", "a,b"]}}"#,
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "tags"}"#,
+        ],
+    );
+    let codes: Vec<_> = responses
+        .iter()
+        .map(|response| {
+            let response: serde_json::Value = serde_json::from_str(response).unwrap();
+            (response["id"].clone(), response["error"]["code"].clone())
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            (serde_json::Value::Null, (-32700).into()),
+            (serde_json::Value::Null, (-32600).into()),
+            (2.into(), (-32601).into()),
+            (3.into(), (-32602).into()),
+            (4.into(), (-32000).into()),
+            (5.into(), (-32000).into()),
+            (6.into(), (-32602).into()),
+            (7.into(), (-32602).into()),
+            (8.into(), serde_json::Value::Null),
+        ],
+        codes
+    );
+    assert!(responses[5].contains("Could not find such tag in database: other"));
+    assert!(responses[6].contains("Tag names could not be blank or contain ',': ' '"));
+    assert!(responses[7].contains("Tag names could not be blank or contain ',': 'a,b'"));
+    assert!(responses[8].contains(r#"["test","test2","test3"]"#));
+}
+
+#[test]
+fn rpc_batch() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let responses = rpc(
+        &db_path,
+        &[
+            r#"[
+                {"jsonrpc": "2.0", "id": 1, "method": "tags"},
+                {"jsonrpc": "2.0", "method": "tag", "params": {"path": "temp_tag_file_1", "tags": ["new"]}},
+                {"jsonrpc": "2.0", "id": 2, "method": "other"},
+                {"jsonrpc": "2.0", "id": 3, "method": "tags"}
+            ]"#
+            .replace('\n', " ")
+            .as_str(),
+            r#"[{"jsonrpc": "2.0", "method": "tags"}]"#,
+            "[]",
+        ],
+    );
+    assert_eq!(
+        vec![
+            r#"[{"id":1,"jsonrpc":"2.0","result":["test","test2","test3"]},{"error":{"code":-32601,"message":"Unknown method: other"},"id":2,"jsonrpc":"2.0"},{"id":3,"jsonrpc":"2.0","result":["test","test2","test3","new"]}]"#,
+            r#"{"error":{"code":-32600,"message":"empty batch"},"id":null,"jsonrpc":"2.0"}"#,
+        ],
+        responses
+    );
+}
@@ -0,0 +1,283 @@
+mod common;
+
+use crate::common::{tag, two_files_multiple_tags_prepare};
+use anytaggy::{Args, Command, entrypoint};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+/// Starts server in background, it's stopped along with tests
+fn serve(db_path: &Path, writable: bool) -> u16 {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Serve {
+            listen: format!("127.0.0.1:{port}"),
+            writable,
+        },
+    };
+    thread::spawn(move || entrypoint(args).unwrap());
+
+    for _ in 0..100 {
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return port;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("server did not start");
+}
+
+fn request(port: u16, method: &str, target: &str, body: &str) -> (u16, String) {
+    request_with_headers(
+        port,
+        method,
+        target,
+        "Host: localhost\r\nContent-Type: application/json\r\n",
+        body,
+    )
+}
+
+fn request_with_headers(
+    port: u16,
+    method: &str,
+    target: &str,
+    headers: &str,
+    body: &str,
+) -> (u16, String) {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(
+        stream,
+        "{method} {target} HTTP/1.1\r\n{headers}Connection: close\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.into())
+}
+
+#[test]
+fn serve_queries() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let port = serve(&db_path, false);
+
+    assert_eq!(
+        (200, r#"["test","test2","test3","test4","test5"]"#.into()),
+        request(port, "GET", "/tags", "")
+    );
+    assert_eq!(
+        (200, r#"["temp_tag_file_1","temp_tag_file_2"]"#.into()),
+        request(port, "GET", "/files?tags=test3", "")
+    );
+    assert_eq!(
+        (200, r#"["temp_tag_file_2"]"#.into()),
+        request(port, "GET", "/files?tags=test3,test4", "")
+    );
+    assert_eq!(
+        (200, "[]".into()),
+        request(port, "GET", "/files?tags=test,test4", "")
+    );
+    assert_eq!(
+        (
+            200,
+            r#"{"path":"temp_tag_file_1","name":"temp_tag_file_1","mime_type":null,"tags":["test","test2","test3"]}"#
+                .into()
+        ),
+        request(port, "GET", "/file?path=temp_tag_file_1", "")
+    );
+    assert_eq!(
+        (
+            200,
+            r#"{"path":"temp_tag_file_2","name":"temp_tag_file_2","mime_type":null,"tags":["test3","test4","test5"]}"#
+                .into()
+        ),
+        request(
+            port,
+            "GET",
+            &format!("/file?path={}", tag_file_2.display()),
+            ""
+        )
+    );
+}
+
+#[test]
+fn serve_errors() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    std::fs::write(temp_dir.path().join("untagged file"), "untagged").unwrap();
+    let port = serve(&db_path, false);
+
+    assert_eq!(
+        (
+            404,
+            r#"{"error":"Database error: Could not find such file in database"}"#.into()
+        ),
+        request(port, "GET", "/file?path=untagged%20file", "")
+    );
+    assert_eq!(
+        (404, r#"{"error":"Could not find specified file"}"#.into()),
+        request(port, "GET", "/file?path=missing", "")
+    );
+    assert_eq!(
+        (
+            400,
+            r#"{"error":"Invalid request: missing 'path' parameter"}"#.into()
+        ),
+        request(port, "GET", "/file", "")
+    );
+    assert_eq!(
+        (404, r#"{"error":"Unknown endpoint: GET /other"}"#.into()),
+        request(port, "GET", "/other", "")
+    );
+    assert_eq!(
+        (
+            403,
+            r#"{"error":"Host 'example.com' is not allowed, use the listen address or localhost"}"#
+                .into()
+        ),
+        request_with_headers(port, "GET", "/tags", "Host: example.com\r\n", "")
+    );
+    assert_eq!(
+        (200, r#"["test","test2","test3"]"#.into()),
+        request_with_headers(port, "GET", "/tags", "Host: 127.0.0.1:1234\r\n", "")
+    );
+    assert_eq!(
+        (403, r#"{"error":"Server is read-only"}"#.into()),
+        request(
+            port,
+            "POST",
+            "/file/tags?path=temp_tag_file_1",
+            r#"["new"]"#
+        )
+    );
+    assert_eq!(
+        (403, r#"{"error":"Server is read-only"}"#.into()),
+        request(
+            port,
+            "DELETE",
+            "/file/tags?path=temp_tag_file_1&tags=test",
+            ""
+        )
+    );
+}
+
+#[test]
+fn serve_writable() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    std::fs::write(temp_dir.path().join("new file"), "new").unwrap();
+    let port = serve(&db_path, true);
+
+    assert_eq!(
+        (
+            200,
+            r#"{"path":"new file","name":"new file","mime_type":null,"tags":["new","other"]}"#
+                .into()
+        ),
+        request(
+            port,
+            "POST",
+            "/file/tags?path=new+file",
+            r#"["new", "other"]"#
+        )
+    );
+    assert_eq!(
+        (400, r#"{"error":"No tags specified"}"#.into()),
+        request(port, "POST", "/file/tags?path=new+file", "[]")
+    );
+    assert_eq!(
+        (
+            415,
+            r#"{"error":"Request body must be sent as application/json"}"#.into()
+        ),
+        request_with_headers(
+            port,
+            "POST",
+            "/file/tags?path=new+file",
+            "Host: localhost\r\nContent-Type: text/plain\r\n",
+            r#"["new"]"#
+        )
+    );
+    assert_eq!(
+        (
+            400,
+            r#"{"error":"Tag names could not be blank or contain ',': ' '"}"#.into()
+        ),
+        request(
+            port,
+            "POST",
+            "/file/tags?path=new+file",
+            r#"["third", " "]"#
+        )
+    );
+    assert_eq!(
+        (
+            400,
+            r#"{"error":"Tag names could not be blank or contain ',': 'a,b'"}"#.into()
+        ),
+        request(port, "POST", "/file/tags?path=new+file", r#"["a,b"]"#)
+    );
+    assert_eq!(
+        (
+            400,
+            r#"{"error":"Tag names could not be blank or contain ',': ''"}"#.into()
+        ),
+        request(
+            port,
+            "DELETE",
+            "/file/tags?path=temp_tag_file_1&tags=test,,test2",
+            ""
+        )
+    );
+    let large_body = format!(r#"["{}"]"#, "a".repeat(70_000));
+    assert_eq!(
+        (
+            413,
+            r#"{"error":"Request body is larger than 65536 bytes"}"#.into()
+        ),
+        request(port, "POST", "/file/tags?path=new+file", &large_body)
+    );
+    assert_eq!(
+        (204, String::new()),
+        request(
+            port,
+            "DELETE",
+            "/file/tags?path=temp_tag_file_1&tags=test,test3",
+            ""
+        )
+    );
+    assert_eq!(
+        (
+            404,
+            r#"{"error":"Database error: File did not have such tag: test"}"#.into()
+        ),
+        request(
+            port,
+            "DELETE",
+            "/file/tags?path=temp_tag_file_1&tags=test",
+            ""
+        )
+    );
+    assert_eq!(
+        (200, r#"["test","test2","test3","new","other"]"#.into()),
+        request(port, "GET", "/tags", "")
+    );
+    assert_eq!(
+        (200, r#"["temp_tag_file_1"]"#.into()),
+        request(port, "GET", "/files?tags=test2", "")
+    );
+}
@@ -1,7 +1,7 @@
 mod common;
 
 use crate::common::{create_random_file, two_files_multiple_tags_prepare};
-use anytaggy::{AppError, Args, Command, entrypoint};
+use anytaggy::{AppError, Args, Command, DatabaseSources, entrypoint};
 use temp_dir::TempDir;
 
 #[test]
@@ -13,6 +13,7 @@ fn no_tag_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -23,6 +24,7 @@ fn no_tag_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: vec![],
+            create: true,
         },
     };
     let out = entrypoint(args);
@@ -40,6 +42,7 @@ fn tag_file() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -50,6 +53,7 @@ fn tag_file() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags_1.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -59,6 +63,7 @@ fn tag_file() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -82,6 +87,7 @@ fn tag_file_in_parent_directory_without_db() {
         command: Command::Tag {
             file_path: tag_file_1.clone(),
             tags: vec!["test".into()],
+            create: true,
         },
     };
     let out = entrypoint(args);
@@ -99,6 +105,7 @@ fn tag_nonexistent_file() {
         command: Command::Tag {
             file_path: nonexistent_file,
             tags: vec!["test".into()],
+            create: true,
         },
     };
     let out = entrypoint(args);
@@ -1,9 +1,13 @@
 mod common;
 
-use anytaggy::{AppError, Args, Command, entrypoint};
+use anytaggy::{
+    AppError, Args, Command, DatabaseSources,
+    db::{Database, DatabaseMode},
+    entrypoint,
+};
 use temp_dir::TempDir;
 
-use crate::common::{create_random_file, two_files_multiple_tags_prepare};
+use crate::common::{create_random_file, tag, two_files_multiple_tags_prepare};
 
 #[test]
 fn no_tags_database() {
@@ -11,7 +15,10 @@ fn no_tags_database() {
 
     let args = Args {
         database_path: None,
-        command: Command::Tags { file_path: None },
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args);
     assert!(matches!(out, Err(AppError::DatabaseNotFound)));
@@ -27,6 +34,7 @@ fn get_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -36,6 +44,7 @@ fn get_tags() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -52,6 +61,7 @@ fn get_all_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -59,7 +69,10 @@ fn get_all_tags() {
 
     let args = Args {
         database_path: Some(db_path.clone()),
-        command: Command::Tags { file_path: None },
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args).unwrap();
     assert_eq!(Some(test_tags.join(",")), out);
@@ -73,7 +86,10 @@ fn get_all_tags() {
 
     let args = Args {
         database_path: Some(db_path.clone()),
-        command: Command::Tags { file_path: None },
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
     };
     let out = entrypoint(args).unwrap();
     assert_eq!(None, out);
@@ -94,6 +110,7 @@ fn tags_file_in_parent_directory_without_db() {
         command: Command::Tag {
             file_path: tag_file_ok.clone(),
             tags: vec!["test".into()],
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -103,8 +120,47 @@ fn tags_file_in_parent_directory_without_db() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file_1.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args);
     assert!(matches!(out, Err(AppError::FileOutsideStructure)));
 }
+
+#[test]
+fn tag_counts() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    tag(&db_path, &tag_file_2, &["empty"]);
+
+    let mut db = Database::new(&DatabaseMode::ReadWrite, &db_path).unwrap();
+    db.delete_tags(&["empty"]).unwrap();
+    db.create_tag("empty").unwrap();
+    assert_eq!(
+        vec![
+            ("empty".to_string(), 0),
+            ("test".into(), 1),
+            ("test2".into(), 1),
+            ("test3".into(), 2),
+            ("test4".into(), 1),
+            ("test5".into(), 1),
+        ],
+        db.get_tag_counts().unwrap()
+    );
+
+    // Missing files are not counted
+    db.set_file_missing("temp_tag_file_2").unwrap();
+    assert_eq!(
+        vec![
+            ("empty".to_string(), 0),
+            ("test".into(), 1),
+            ("test2".into(), 1),
+            ("test3".into(), 1),
+            ("test4".into(), 0),
+            ("test5".into(), 0),
+        ],
+        db.get_tag_counts().unwrap()
+    );
+}
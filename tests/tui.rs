@@ -0,0 +1,24 @@
+mod common;
+
+use crate::common::{tag, two_files_multiple_tags_prepare};
+use std::process::{self, Stdio};
+
+#[test]
+fn tui_needs_terminal() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+
+    let output = process::Command::new(env!("CARGO_BIN_EXE_anytaggy"))
+        .arg("--database-path")
+        .arg(&db_path)
+        .arg("tui")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        "ERROR: Terminal interface needs an interactive terminal\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+}
@@ -1,7 +1,7 @@
 mod common;
 
 use crate::common::{create_random_file, two_files_multiple_tags_prepare};
-use anytaggy::{AppError, Args, Command, db::DatabaseError, entrypoint};
+use anytaggy::{AppError, Args, Command, DatabaseSources, db::DatabaseError, entrypoint};
 use std::path::PathBuf;
 use temp_dir::TempDir;
 
@@ -29,6 +29,7 @@ fn no_tag_tags() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -55,6 +56,7 @@ fn untag_file() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -64,6 +66,7 @@ fn untag_file() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -97,6 +100,7 @@ fn untag_file() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -113,6 +117,7 @@ fn files_clean_after_delete_untag() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -122,6 +127,7 @@ fn files_clean_after_delete_untag() {
         database_path: Some(db_path.clone()),
         command: Command::Tags {
             file_path: Some(tag_file.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -141,6 +147,7 @@ fn files_clean_after_delete_untag() {
         database_path: Some(db_path.clone()),
         command: Command::Files {
             tags: Some(test_tags.clone()),
+            sources: DatabaseSources::default(),
         },
     };
     let out = entrypoint(args).unwrap();
@@ -158,6 +165,7 @@ fn no_such_file() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -188,6 +196,7 @@ fn no_such_tag_on_file() {
         command: Command::Tag {
             file_path: tag_file.clone(),
             tags: test_tags.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -198,6 +207,7 @@ fn no_such_tag_on_file() {
         command: Command::Tag {
             file_path: tag_file_2.clone(),
             tags: test_tags_2.clone(),
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -232,6 +242,7 @@ fn untag_file_in_parent_directory_without_db() {
         command: Command::Tag {
             file_path: tag_file_ok.clone(),
             tags: vec!["test".into()],
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -257,6 +268,7 @@ fn untag_nonexistent_file() {
         command: Command::Tag {
             file_path: file.clone(),
             tags: vec!["test".into()],
+            create: true,
         },
     };
     let out = entrypoint(args).unwrap();
@@ -0,0 +1,140 @@
+mod common;
+
+use crate::common::{create_random_file, tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, ViewCommand, entrypoint};
+use std::path::{Path, PathBuf};
+
+fn untag(db_path: &Path, file_path: &Path, tags: &[String]) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Untag {
+            file_path: file_path.to_path_buf(),
+            tags: tags.to_vec(),
+        },
+    };
+    entrypoint(args).unwrap();
+}
+
+fn build(db_path: &Path, directory: &Path, tags: &[&str]) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::View {
+            command: ViewCommand::Build {
+                directory: directory.to_path_buf(),
+                tags: tags.iter().map(|tag| (*tag).into()).collect(),
+            },
+        },
+    };
+    entrypoint(args)
+}
+
+fn sync(db_path: Option<PathBuf>, directory: &Path) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: db_path,
+        command: Command::View {
+            command: ViewCommand::Sync {
+                directory: directory.to_path_buf(),
+            },
+        },
+    };
+    entrypoint(args)
+}
+
+#[test]
+fn view_build_links_matching_files() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let view_path = temp_dir.path().join("view");
+
+    let out = build(&db_path, &view_path, &["test3"]).unwrap();
+    assert_eq!(
+        Some(
+            "Linked: temp_tag_file_1\n\
+            Linked: temp_tag_file_2\n\
+            Unchanged: 0 links"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        tag_file_1,
+        std::fs::read_link(view_path.join("temp_tag_file_1")).unwrap()
+    );
+    assert_eq!(
+        tag_file_2,
+        std::fs::read_link(view_path.join("temp_tag_file_2")).unwrap()
+    );
+    assert!(view_path.join(".anytaggy-view").is_file());
+
+    // Building again keeps existing links
+    let out = build(&db_path, &view_path, &["test4"]).unwrap();
+    assert_eq!(
+        Some(
+            "Unlinked: temp_tag_file_1\n\
+            Unchanged: 1 links"
+                .into()
+        ),
+        out
+    );
+}
+
+#[test]
+fn view_sync_updates_links() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    let view_path = temp_dir.path().join("view");
+    build(&db_path, &view_path, &["test3"]).unwrap();
+    std::fs::write(view_path.join("notes"), "kept").unwrap();
+    let other_dir = temp_dir::TempDir::new().unwrap();
+    std::os::unix::fs::symlink(other_dir.path(), view_path.join("other")).unwrap();
+
+    std::fs::create_dir(temp_dir.path().join("nested")).unwrap();
+    let nested_file = create_random_file(&temp_dir.path().join("nested"), "temp_tag_file_2");
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    tag(&db_path, &nested_file, &["test3"]);
+    untag(&db_path, &tag_file_1, &["test3".into()]);
+
+    // Database is taken from the view
+    let out = sync(None, &view_path).unwrap();
+    assert_eq!(
+        Some(
+            "Unlinked: temp_tag_file_1\n\
+            Linked: temp_tag_file_2\n\
+            Linked: temp_tag_file_2 (2)\n\
+            Unchanged: 0 links"
+                .into()
+        ),
+        out
+    );
+    assert_eq!(
+        nested_file,
+        std::fs::read_link(view_path.join("temp_tag_file_2")).unwrap()
+    );
+    assert_eq!(
+        tag_file_2,
+        std::fs::read_link(view_path.join("temp_tag_file_2 (2)")).unwrap()
+    );
+    assert!(view_path.join("notes").is_file());
+    assert!(view_path.join("other").is_dir());
+
+    let out = sync(Some(db_path), &view_path).unwrap();
+    assert_eq!(Some("Unchanged: 2 links".into()), out);
+}
+
+#[test]
+fn view_rejects_other_directories() {
+    let (db_path, tag_file_1, _, test_tags_1, _, temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    let directory = temp_dir.path().join("other");
+    std::fs::create_dir(&directory).unwrap();
+
+    let err = sync(Some(db_path.clone()), &directory).unwrap_err();
+    assert!(matches!(err, AppError::NotAView(_)));
+
+    std::fs::write(directory.join("notes"), "kept").unwrap();
+    let err = build(&db_path, &directory, &["test"]).unwrap_err();
+    assert!(matches!(err, AppError::ViewDirectoryNotEmpty(_)));
+}
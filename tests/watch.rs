@@ -0,0 +1,169 @@
+mod common;
+
+use crate::common::{create_random_file, file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{Args, Command, DatabaseSources, entrypoint};
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+fn files(db_path: &Path, tags: &[&str]) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Files {
+            tags: Some(tags.iter().map(|tag| (*tag).into()).collect()),
+            sources: DatabaseSources::default(),
+        },
+    };
+    entrypoint(args).unwrap()
+}
+
+/// Runs watch in background for a while, changes are made by the closure meanwhile
+fn watch_while(db_path: &Path, change: impl FnOnce()) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Watch { timeout: Some(2) },
+    };
+    let handle = thread::spawn(move || assert_eq!(None, entrypoint(args).unwrap()));
+    // Watches are set up before changes are made
+    thread::sleep(Duration::from_millis(500));
+    change();
+    handle.join().unwrap();
+}
+
+fn missing_paths(db_path: &Path) -> Vec<String> {
+    let connection = rusqlite::Connection::open(db_path).unwrap();
+    let mut query = connection
+        .prepare("SELECT path FROM files WHERE missing = 1 ORDER BY path")
+        .unwrap();
+    query
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn watch_follows_moves() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    let directory = temp_dir.path().join("directory");
+    std::fs::create_dir(&directory).unwrap();
+    let nested_file = create_random_file(&directory, "nested");
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    tag(&db_path, &nested_file, &["nested"]);
+
+    let moved_file = directory.join("moved");
+    let moved_directory = temp_dir.path().join("renamed");
+    watch_while(&db_path, || {
+        std::fs::rename(&tag_file_1, &moved_file).unwrap();
+        std::fs::rename(&directory, &moved_directory).unwrap();
+    });
+
+    let moved_file = moved_directory.join("moved");
+    assert_eq!(
+        Some(moved_file.display().to_string()),
+        files(&db_path, &["test"])
+    );
+    assert_eq!(
+        Some(moved_directory.join("nested").display().to_string()),
+        files(&db_path, &["nested"])
+    );
+    assert_eq!(
+        Some(format!(
+            "{}\n{}",
+            moved_file.display(),
+            tag_file_2.display()
+        )),
+        files(&db_path, &["test3"])
+    );
+    assert!(missing_paths(&db_path).is_empty());
+
+    // Moved file could be untagged by its new path
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Untag {
+            file_path: moved_file,
+            tags: vec!["test".into()],
+        },
+    };
+    entrypoint(args).unwrap();
+}
+
+#[test]
+fn watch_marks_missing_and_rehashes() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    let outside = tempfile_outside(temp_dir.path());
+
+    watch_while(&db_path, || {
+        std::fs::remove_file(&tag_file_1).unwrap();
+        std::fs::write(&tag_file_2, "new contents").unwrap();
+    });
+    assert_eq!(vec!["temp_tag_file_1"], missing_paths(&db_path));
+
+    // Modified file is found by its new contents
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: Some(tag_file_2.clone()),
+            sources: DatabaseSources::default(),
+        },
+    };
+    assert_eq!(Some("test3,test4,test5".into()), entrypoint(args).unwrap());
+
+    // File is back
+    watch_while(&db_path, || {
+        std::fs::write(&outside, "restored").unwrap();
+        std::fs::rename(&outside, &tag_file_1).unwrap();
+    });
+    assert!(missing_paths(&db_path).is_empty());
+}
+
+#[test]
+fn watch_moves_onto_tracked_file() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+
+    watch_while(&db_path, || {
+        std::fs::rename(&tag_file_1, &tag_file_2).unwrap();
+    });
+    assert_eq!(
+        Some(tag_file_2.display().to_string()),
+        files(&db_path, &["test"])
+    );
+    assert_eq!(None, files(&db_path, &["test4"]));
+
+    // Overwritten file is gone, so file at its path could still be hashed again
+    watch_while(&db_path, || {
+        std::fs::write(&tag_file_2, "new contents").unwrap();
+    });
+    assert_eq!(
+        Some("test,test2,test3".into()),
+        file_tags(&db_path, &tag_file_2)
+    );
+    // Tags of overwritten file are kept
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Tags {
+            file_path: None,
+            sources: DatabaseSources::default(),
+        },
+    };
+    assert_eq!(
+        Some("test,test2,test3,test4,test5".into()),
+        entrypoint(args).unwrap()
+    );
+}
+
+fn tempfile_outside(root: &Path) -> PathBuf {
+    root.parent()
+        .unwrap()
+        .join(format!("{}-outside", root.file_name().unwrap().display()))
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use crate::common::{file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{
+    Args, Command, DbCommand, XattrCommand,
+    db::{ImportMode, Setting},
+    entrypoint,
+};
+use std::path::{Path, PathBuf};
+
+const XATTR_TAGS: &str = "user.xdg.tags";
+
+fn untag(db_path: &Path, file_path: &Path, tags: &[String]) {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Untag {
+            file_path: file_path.to_path_buf(),
+            tags: tags.to_vec(),
+        },
+    };
+    entrypoint(args).unwrap();
+}
+
+fn xattr(db_path: &Path, command: XattrCommand) -> Option<String> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Xattr { command },
+    };
+    entrypoint(args).unwrap()
+}
+
+fn read_xattr(file_path: &Path) -> Option<String> {
+    xattr::get(file_path, XATTR_TAGS)
+        .unwrap()
+        .map(|value| String::from_utf8(value).unwrap())
+}
+
+#[test]
+fn export_writes_xattr() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    std::fs::remove_file(&tag_file_2).unwrap();
+
+    let out = xattr(&db_path, XattrCommand::Export);
+    assert_eq!(
+        Some("Missing: temp_tag_file_2\nExported: 1 files".into()),
+        out
+    );
+    assert_eq!(Some("test,test2,test3".into()), read_xattr(&tag_file_1));
+}
+
+#[test]
+fn import_reads_xattr() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, _, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    xattr::set(&tag_file_1, XATTR_TAGS, b"test,other").unwrap();
+    std::fs::create_dir(temp_dir.path().join("nested")).unwrap();
+    let nested_file = temp_dir.path().join("nested").join("file");
+    std::fs::write(&nested_file, "nested").unwrap();
+    xattr::set(&nested_file, XATTR_TAGS, b"nested, tags").unwrap();
+
+    let out = xattr(
+        &db_path,
+        XattrCommand::Import {
+            paths: Vec::new(),
+            mode: ImportMode::Merge,
+        },
+    );
+    assert_eq!(
+        Some("Added: nested/file\nUpdated: temp_tag_file_1 (+other)\nUnchanged: 0 files".into()),
+        out
+    );
+    assert_eq!(
+        Some("test,test2,test3,other".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+    assert_eq!(
+        Some("nested,tags".into()),
+        file_tags(&db_path, &nested_file)
+    );
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn import_only_specified_paths() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, _, _temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    xattr::set(&tag_file_1, XATTR_TAGS, b"other").unwrap();
+    xattr::set(&tag_file_2, XATTR_TAGS, b"other").unwrap();
+
+    let out = xattr(
+        &db_path,
+        XattrCommand::Import {
+            paths: vec![PathBuf::from("temp_tag_file_2")],
+            mode: ImportMode::DryRun,
+        },
+    );
+    assert_eq!(
+        Some("Would add: temp_tag_file_2\nUnchanged: 0 files".into()),
+        out
+    );
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn sync_keeps_xattr_up_to_date() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &["first"]);
+    assert_eq!(None, read_xattr(&tag_file_1));
+
+    let args = Args {
+        database_path: Some(db_path.clone()),
+        command: Command::Db {
+            command: DbCommand::Set {
+                setting: Setting::XattrSync,
+                enabled: true,
+            },
+        },
+    };
+    entrypoint(args).unwrap();
+
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    assert_eq!(
+        Some("first,test,test2,test3".into()),
+        read_xattr(&tag_file_1)
+    );
+
+    untag(&db_path, &tag_file_1, &["first".into(), "test".into()]);
+    assert_eq!(Some("test2,test3".into()), read_xattr(&tag_file_1));
+
+    untag(&db_path, &tag_file_1, &["test2".into(), "test3".into()]);
+    assert_eq!(None, read_xattr(&tag_file_1));
+}
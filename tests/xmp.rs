@@ -0,0 +1,191 @@
+mod common;
+
+use crate::common::{file_tags, tag, two_files_multiple_tags_prepare};
+use anytaggy::{AppError, Args, Command, XmpCommand, db::ImportMode, entrypoint};
+use std::path::{Path, PathBuf};
+
+const DARKTABLE_SIDECAR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="XMP Core 4.4.0-Exiv2">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+   xmp:Rating="4">
+   <dc:title>
+    <rdf:Alt>
+     <rdf:li xml:lang="x-default">Sunset</rdf:li>
+    </rdf:Alt>
+   </dc:title>
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>darktable|format|jpg</rdf:li>
+     <rdf:li>sunset &amp; sea</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+"#;
+
+fn xmp(db_path: &Path, command: XmpCommand) -> Result<Option<String>, AppError> {
+    let args = Args {
+        database_path: Some(db_path.to_path_buf()),
+        command: Command::Xmp { command },
+    };
+    entrypoint(args)
+}
+
+fn import(db_path: &Path, paths: Vec<PathBuf>) -> Result<Option<String>, AppError> {
+    xmp(
+        db_path,
+        XmpCommand::Import {
+            paths,
+            mode: ImportMode::Merge,
+        },
+    )
+}
+
+fn sidecar_path(file_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.xmp", file_path.display()))
+}
+
+#[test]
+fn export_creates_sidecars() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, test_tags_2, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    tag(&db_path, &tag_file_2, &test_tags_2);
+    std::fs::remove_file(&tag_file_2).unwrap();
+
+    let out = xmp(&db_path, XmpCommand::Export).unwrap();
+    assert_eq!(
+        Some("Missing: temp_tag_file_2\nExported: 1 files".into()),
+        out
+    );
+    let sidecar = std::fs::read_to_string(sidecar_path(&tag_file_1)).unwrap();
+    assert!(sidecar.contains("<rdf:li>test2</rdf:li>"));
+    assert!(!sidecar_path(&tag_file_2).exists());
+
+    // Sidecar is readable back
+    let other_db_path = temp_dir.path().join("other.db");
+    tag(&other_db_path, &tag_file_1, &["other"]);
+    let out = import(&other_db_path, Vec::new()).unwrap();
+    assert_eq!(
+        Some("Updated: temp_tag_file_1 (+test,test2,test3)\nUnchanged: 0 files".into()),
+        out
+    );
+}
+
+#[test]
+fn export_keeps_other_metadata() {
+    let (db_path, tag_file_1, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+    std::fs::write(sidecar_path(&tag_file_1), DARKTABLE_SIDECAR).unwrap();
+    tag(&db_path, &tag_file_1, &["b&w", "landscape"]);
+
+    xmp(&db_path, XmpCommand::Export).unwrap();
+    let sidecar = std::fs::read_to_string(sidecar_path(&tag_file_1)).unwrap();
+    assert!(sidecar.contains(r#"xmp:Rating="4""#));
+    assert!(sidecar.contains(r#"<rdf:li xml:lang="x-default">Sunset</rdf:li>"#));
+    assert!(sidecar.contains("<rdf:li>b&amp;w</rdf:li>\n<rdf:li>landscape</rdf:li>"));
+    assert!(!sidecar.contains("sunset &amp; sea"));
+    assert_eq!(1, sidecar.matches("<dc:subject>").count());
+}
+
+#[test]
+fn export_adds_subject_to_existing_description() {
+    let (db_path, tag_file_1, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+    let without_subject = DARKTABLE_SIDECAR.replace(
+        "   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>darktable|format|jpg</rdf:li>
+     <rdf:li>sunset &amp; sea</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+",
+        "",
+    );
+    std::fs::write(sidecar_path(&tag_file_1), without_subject).unwrap();
+    tag(&db_path, &tag_file_1, &["landscape"]);
+
+    xmp(&db_path, XmpCommand::Export).unwrap();
+    let sidecar = std::fs::read_to_string(sidecar_path(&tag_file_1)).unwrap();
+    assert!(sidecar.contains(r#"xmp:Rating="4""#));
+    assert!(sidecar.contains("<rdf:li>landscape</rdf:li>"));
+    assert!(sidecar.contains("Sunset"));
+}
+
+#[test]
+fn import_reads_keywords() {
+    let (db_path, tag_file_1, tag_file_2, test_tags_1, _, temp_dir) =
+        two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    std::fs::write(sidecar_path(&tag_file_1), DARKTABLE_SIDECAR).unwrap();
+    std::fs::write(temp_dir.path().join("missing.jpg.xmp"), DARKTABLE_SIDECAR).unwrap();
+
+    let out = import(&db_path, Vec::new()).unwrap();
+    assert_eq!(
+        Some(format!(
+            "Missing: {}\nUpdated: temp_tag_file_1 (+darktable|format|jpg,sunset & sea)\nUnchanged: 0 files",
+            temp_dir.path().join("missing.jpg").display()
+        )),
+        out
+    );
+    assert_eq!(
+        Some("test,test2,test3,darktable|format|jpg,sunset & sea".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+    assert_eq!(None, file_tags(&db_path, &tag_file_2));
+}
+
+#[test]
+fn import_rejects_invalid_sidecar() {
+    let (db_path, tag_file_1, _, test_tags_1, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &test_tags_1);
+    std::fs::write(
+        sidecar_path(&tag_file_1),
+        "<x:xmpmeta><rdf:RDF></x:xmpmeta>",
+    )
+    .unwrap();
+
+    let err = import(&db_path, vec![sidecar_path(&tag_file_1)]).unwrap_err();
+    assert!(matches!(err, AppError::InvalidXmp(..)));
+}
+
+#[test]
+fn import_removes_stale_keywords() {
+    let (db_path, tag_file_1, _, _, _, _temp_dir) = two_files_multiple_tags_prepare();
+    tag(&db_path, &tag_file_1, &["own", "sunset & sea"]);
+    std::fs::write(sidecar_path(&tag_file_1), DARKTABLE_SIDECAR).unwrap();
+    import(&db_path, Vec::new()).unwrap();
+
+    std::fs::write(
+        sidecar_path(&tag_file_1),
+        DARKTABLE_SIDECAR.replace("     <rdf:li>darktable|format|jpg</rdf:li>\n", ""),
+    )
+    .unwrap();
+    let out = import(&db_path, Vec::new()).unwrap();
+    assert_eq!(
+        Some(
+            "Updated: temp_tag_file_1 (-darktable|format|jpg)\n\
+            Deleted tag: darktable|format|jpg\n\
+            Unchanged: 0 files"
+                .into()
+        ),
+        out
+    );
+
+    // Tags added by user stay, even when sidecar had them as well
+    std::fs::write(
+        sidecar_path(&tag_file_1),
+        DARKTABLE_SIDECAR
+            .replace("<dc:subject>", "<dc:description>")
+            .replace("</dc:subject>", "</dc:description>"),
+    )
+    .unwrap();
+    let out = import(&db_path, Vec::new()).unwrap();
+    assert_eq!(Some("Unchanged: 1 files".into()), out);
+    assert_eq!(
+        Some("own,sunset & sea".into()),
+        file_tags(&db_path, &tag_file_1)
+    );
+}